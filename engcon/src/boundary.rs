@@ -0,0 +1,101 @@
+//! Boundary-value test cases, i.e. values just outside, on and just inside every bound.
+
+use crate::RuleContract;
+
+/// A value of a field at the bound of a rule, see [BoundaryCases::boundary_values]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundaryValue {
+    /// the rule whose bound is tested, the value is assigned to [RuleContract::field]
+    pub rule: &'static RuleContract,
+    pub value: f64,
+}
+
+/// Generates boundary-value test cases from a valid base instance.
+///
+/// The derive macro implements it for types whose validated fields are primitive numbers.
+/// The type parameter `C` is the context of types that implement
+/// [ValidatorWithContext](crate::ValidatorWithContext), `()` otherwise.
+pub trait BoundaryCases<C = ()>: Sized {
+    /// The values just outside, on and just inside the bound of every rule, e.g. `2, 3, 4`
+    /// for `x >= 3` or `-ε, 0.0, ε` for `x > 0.0` on a float field.
+    ///
+    /// The bounds of cross-field rules are taken from `self`.
+    fn boundary_values(&self, ctx: &C) -> Vec<BoundaryValue>;
+
+    /// sets the field with the given name to `value` converted to the field type
+    fn set_boundary_value(&mut self, field: &str, value: f64);
+
+    /// Checks every rule in `f64` arithmetic, independent of the generated [Validator](crate::Validator)
+    /// implementation. It gives the expected validity of the test cases.
+    fn contracts_hold(&self, ctx: &C) -> bool;
+
+    /// Copies of `self` with a single field set to a [boundary value](Self::boundary_values),
+    /// each with its expected validity
+    fn boundary_cases_with(&self, ctx: &C) -> Vec<(Self, bool)>
+    where
+        Self: Clone,
+    {
+        self.boundary_values(ctx)
+            .into_iter()
+            .map(|bv| {
+                let mut case = self.clone();
+                case.set_boundary_value(bv.rule.field, bv.value);
+                let valid = case.contracts_hold(ctx);
+                (case, valid)
+            })
+            .collect()
+    }
+
+    /// the [boundary cases](Self::boundary_cases_with) of types without a context
+    fn boundary_cases(&self) -> Vec<(Self, bool)>
+    where
+        Self: BoundaryCases + Clone,
+    {
+        BoundaryCases::<()>::boundary_cases_with(self, &())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_fixtures::DistillationColumn;
+    use crate::*;
+
+    fn base() -> DistillationColumn {
+        DistillationColumn {
+            trays: 10,
+            feed_place: 1,
+            distiliate_to_feed_ratio: 0.5,
+        }
+    }
+
+    #[test]
+    fn cases_lie_around_every_bound() {
+        let cases = base().boundary_cases();
+        assert_eq!(cases.len(), 3 * 5);
+
+        let trays: Vec<_> = cases[..3].iter().map(|(c, v)| (c.trays, *v)).collect();
+        assert_eq!(trays, [(2, false), (3, true), (4, true)]);
+        let ratio: Vec<_> = cases[9..]
+            .iter()
+            .map(|(c, v)| (c.distiliate_to_feed_ratio, *v))
+            .collect();
+        assert_eq!(
+            ratio,
+            [
+                (-f32::EPSILON, false),
+                (0.0, false),
+                (f32::EPSILON, true),
+                (1.0 - f32::EPSILON, true),
+                (1.0, false),
+                (1.0 + f32::EPSILON, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn expected_validity_matches_validation() {
+        for (case, valid) in base().boundary_cases() {
+            assert_eq!(case.validate().is_ok(), valid, "{:?}", case);
+        }
+    }
+}
@@ -0,0 +1,297 @@
+//! Compositions of mixtures, i.e. mole or mass fractions of named components.
+
+use std::ops::Index;
+
+use crate::{
+    types::{check_fractions, normalizing_sum, MoleFractionVec},
+    ValidationError, ValidationReport, Validator,
+};
+
+/// The basis of the fractions of a [Composition]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Basis {
+    #[default]
+    Mole,
+    Mass,
+}
+
+/// The fractions of the components of a mixture.
+///
+/// The contract is the one of [MoleFractionVec]: every fraction lies in `[0, 1]` and the
+/// fractions sum up to 1 within the tolerance. The fractions are stored in `F`, i.e.
+/// `Composition<[f64; N]>` for a fixed number of components or `Composition` with a [Vec]
+/// for a dynamic number.
+///
+/// ```
+/// use engcon::*;
+///
+/// let mut x = Composition::mole(["ethanol", "water"], vec![0.4, 0.6000001]);
+/// assert!(x.validate().is_err());
+/// x.normalize().unwrap();
+/// assert!(x.validate().is_ok());
+///
+/// let w = x.to_mass(&[46.07, 18.015]).unwrap();
+/// assert!((w["ethanol"] - 0.63).abs() < 0.01);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Composition<F = Vec<f64>> {
+    components: Vec<String>,
+    fractions: F,
+    basis: Basis,
+    tolerance: f64,
+}
+
+impl<F: AsRef<[f64]> + AsMut<[f64]> + Clone> Composition<F> {
+    /// the allowed deviation of the sum from 1 by default, as for [MoleFractionVec]
+    pub const DEFAULT_TOLERANCE: f64 = MoleFractionVec::TOLERANCE;
+
+    /// generates a composition with the given component names, fractions and basis
+    pub fn new<S: Into<String>>(
+        components: impl IntoIterator<Item = S>,
+        fractions: F,
+        basis: Basis,
+    ) -> Self {
+        Composition {
+            components: components.into_iter().map(Into::into).collect(),
+            fractions,
+            basis,
+            tolerance: Self::DEFAULT_TOLERANCE,
+        }
+    }
+
+    /// generates a composition of mole fractions
+    pub fn mole<S: Into<String>>(components: impl IntoIterator<Item = S>, fractions: F) -> Self {
+        Self::new(components, fractions, Basis::Mole)
+    }
+
+    /// generates a composition of mass fractions
+    pub fn mass<S: Into<String>>(components: impl IntoIterator<Item = S>, fractions: F) -> Self {
+        Self::new(components, fractions, Basis::Mass)
+    }
+
+    /// sets the allowed deviation of the sum from 1
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// the names of the components in the order of the fractions
+    pub fn components(&self) -> &[String] {
+        &self.components
+    }
+
+    /// the fractions in the order of the components
+    pub fn fractions(&self) -> &[f64] {
+        self.fractions.as_ref()
+    }
+
+    /// the basis of the fractions
+    pub fn basis(&self) -> Basis {
+        self.basis
+    }
+
+    /// the allowed deviation of the sum from 1
+    pub fn tolerance(&self) -> f64 {
+        self.tolerance
+    }
+
+    /// the number of components
+    pub fn len(&self) -> usize {
+        self.fractions().len()
+    }
+
+    /// true if there are no components
+    pub fn is_empty(&self) -> bool {
+        self.fractions().is_empty()
+    }
+
+    /// the fraction of the component with the given name
+    pub fn get(&self, component: &str) -> Option<f64> {
+        self.index_of(component).map(|i| self.fractions()[i])
+    }
+
+    /// sets the fraction of the component with the given name, the composition may be
+    /// invalid afterwards, see [normalize](Self::normalize)
+    pub fn set(&mut self, component: &str, fraction: f64) -> Option<f64> {
+        let i = self.index_of(component)?;
+        Some(std::mem::replace(&mut self.fractions.as_mut()[i], fraction))
+    }
+
+    fn index_of(&self, component: &str) -> Option<usize> {
+        self.components.iter().position(|c| c == component)
+    }
+
+    /// the sum of all fractions
+    pub fn sum(&self) -> f64 {
+        self.fractions().iter().sum()
+    }
+
+    /// Scales the fractions to sum up to 1, e.g. to repair rounding drift in inputs.
+    ///
+    /// Fails if a fraction is negative or the sum is not positive.
+    pub fn normalize(&mut self) -> Result<(), ValidationError> {
+        let sum = normalizing_sum("Composition", self.fractions())?;
+        self.fractions.as_mut().iter_mut().for_each(|x| *x /= sum);
+        Ok(())
+    }
+
+    /// converts mole fractions into mass fractions, the molar masses are given in the order
+    /// of the components
+    pub fn to_mass(&self, molar_masses: &[f64]) -> Result<Self, ValidationError> {
+        self.to_basis(Basis::Mass, molar_masses)
+    }
+
+    /// converts mass fractions into mole fractions, the molar masses are given in the order
+    /// of the components
+    pub fn to_mole(&self, molar_masses: &[f64]) -> Result<Self, ValidationError> {
+        self.to_basis(Basis::Mole, molar_masses)
+    }
+
+    /// converts the fractions into the given basis, a copy is returned if the basis is the same
+    pub fn to_basis(&self, basis: Basis, molar_masses: &[f64]) -> Result<Self, ValidationError> {
+        if molar_masses.len() != self.len() || !molar_masses.iter().all(|m| *m > 0.0) {
+            return Err(ValidationError::new(
+                format!(
+                    "expected {} positive molar masses, found {:?}",
+                    self.len(),
+                    molar_masses
+                ),
+                "Composition".to_owned(),
+            ));
+        }
+
+        let mut converted = self.clone();
+        if basis == self.basis {
+            return Ok(converted);
+        }
+        converted.basis = basis;
+        for (x, m) in converted.fractions.as_mut().iter_mut().zip(molar_masses) {
+            match basis {
+                // w_i = x_i M_i / sum_j x_j M_j
+                Basis::Mass => *x *= m,
+                // x_i = (w_i / M_i) / sum_j w_j / M_j
+                Basis::Mole => *x /= m,
+            }
+        }
+        converted.normalize()?;
+        Ok(converted)
+    }
+
+    /// the name of the component at index `i`, the index if the components are not named
+    fn component_name(&self, i: usize) -> String {
+        match self.components.get(i) {
+            Some(name) => name.clone(),
+            None => format!("fractions[{}]", i),
+        }
+    }
+}
+
+impl<F: AsRef<[f64]> + AsMut<[f64]> + Clone> Index<&str> for Composition<F> {
+    type Output = f64;
+
+    /// gets the fraction of the component with the given name, panics for unknown components
+    fn index(&self, component: &str) -> &Self::Output {
+        let i = self
+            .index_of(component)
+            .unwrap_or_else(|| panic!("unknown component '{}'", component));
+        &self.fractions()[i]
+    }
+}
+
+impl<F: AsRef<[f64]> + AsMut<[f64]> + Clone> Validator for Composition<F> {
+    fn validate(&self) -> Result<(), ValidationError> {
+        self.validate_all().into_result()
+    }
+
+    fn validate_all(&self) -> ValidationReport {
+        let src = "Composition";
+        let mut report = ValidationReport::new(src.to_owned());
+        if !self.components.is_empty() && self.components.len() != self.len() {
+            report.push(ValidationError::new(
+                format!(
+                    "{} component names given for {} fractions",
+                    self.components.len(),
+                    self.len()
+                ),
+                src.to_owned(),
+            ));
+        }
+
+        check_fractions(
+            &mut report,
+            self.fractions(),
+            self.basis,
+            self.tolerance,
+            |i| self.component_name(i),
+            "fractions",
+        );
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn fractions_are_non_negative_and_sum_up_to_one() {
+        let x = Composition::mole(["benzene", "toluene"], [0.3, 0.7]);
+        assert!(x.validate().is_ok());
+        assert_eq!(x["toluene"], 0.7);
+        assert_eq!(x.get("xylene"), None);
+
+        let x = Composition::mole(["benzene", "toluene"], [-0.1, 1.1]);
+        let report = x.validate_all();
+        let rules: Vec<_> = report.errors().iter().map(|e| e.rule()).collect();
+        assert_eq!(rules, [Some("benzene >= 0.0"), Some("toluene <= 1.0")]);
+
+        let x = Composition::mole(["benzene", "toluene"], [0.3, 0.6]);
+        let err = x.validate().unwrap_err();
+        assert_eq!(err.rule(), Some("fractions sum >= 0.999999999"));
+        assert_eq!(err.violation().unwrap().value, "0.8999999999999999");
+        assert!(x.clone().with_tolerance(0.2).validate().is_ok());
+    }
+
+    #[test]
+    fn normalize_repairs_drift() {
+        let mut x = Composition::mole(["a", "b", "c"], vec![0.2, 0.3, 0.5001]);
+        x.normalize().unwrap();
+        assert!(x.validate().is_ok());
+
+        let mut x = Composition::mole(["a", "b"], vec![-0.2, 1.0]);
+        assert!(x.normalize().is_err());
+    }
+
+    #[test]
+    fn basis_is_converted_with_molar_masses() {
+        let x = Composition::mole(["ethanol", "water"], [0.5, 0.5]);
+        let w = x.to_mass(&[46.07, 18.015]).unwrap();
+        assert_eq!(w.basis(), Basis::Mass);
+        assert!((w["ethanol"] - 46.07 / (46.07 + 18.015)).abs() < 1e-12);
+
+        let back = w.to_mole(&[46.07, 18.015]).unwrap();
+        assert!((back["ethanol"] - 0.5).abs() < 1e-12);
+        assert!(x.to_mass(&[46.07]).is_err());
+    }
+
+    #[derive(Debug, Clone, engcon_macros::Validatable)]
+    struct Feed {
+        #[validate_value(x > 0.0)]
+        flow: f64,
+        #[validate(nested)]
+        composition: Composition,
+    }
+
+    #[test]
+    fn compositions_are_validated_as_nested_fields() {
+        let feed = Feed {
+            flow: 1.0,
+            composition: Composition::mole(["a", "b"], vec![0.5, -0.5]),
+        };
+        let report = feed.validate_all();
+        let fields: Vec<_> = report.errors().iter().filter_map(|e| e.field()).collect();
+        assert_eq!(fields, ["composition.b", "composition.fractions"]);
+    }
+}
@@ -0,0 +1,166 @@
+//! Runtime metadata of the contracts defined by the `Validatable` derive macro.
+//!
+//! The metadata allows tools like GUIs, documentation generators or optimizers to read the
+//! rules without duplicating them.
+
+use std::fmt::Display;
+
+/// A comparison operator that is supported in a contract
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CmpOp {
+    /// `x < rhs`
+    Lt,
+    /// `x <= rhs`
+    Le,
+    /// `x > rhs`
+    Gt,
+    /// `x >= rhs`
+    Ge,
+}
+
+/// Describes a single rule of a contract, e.g. `x < trays` on the field `feed_place`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RuleContract {
+    /// a unique and readable identifier of the rule, e.g. `feed_place < trays`
+    pub id: &'static str,
+    /// the name of the field the rule belongs to
+    pub field: &'static str,
+    /// the comparison operator
+    pub op: CmpOp,
+    /// the right hand side expression as written in the contract
+    pub rhs: &'static str,
+    /// the bound if the right hand side is a numeric literal
+    pub bound: Option<f64>,
+    /// the name of the sibling field if the right hand side refers to a field on the same type
+    pub sibling: Option<&'static str>,
+    /// true if the right hand side depends on a context, see [ValidatorWithContext](crate::ValidatorWithContext)
+    pub uses_context: bool,
+}
+
+/// Describes the contract of a field, i.e. the list of its rules
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FieldContract {
+    /// the name of the field
+    pub field: &'static str,
+    /// the type of the field as written in the struct definition
+    pub ty: &'static str,
+    /// the unit given by `#[unit("bar")]`, e.g. to show it in a GUI
+    pub unit: Option<&'static str>,
+    /// the rules that have to hold for the field
+    pub rules: &'static [RuleContract],
+}
+
+/// Provides the metadata of all contracts of a type.
+///
+/// Using the derive macro `Validatable` generates an implementation.
+pub trait Contracts {
+    /// the name of the type
+    const TYPE_NAME: &'static str;
+
+    /// the names of all fields in declaration order, including the fields without rules
+    const FIELDS: &'static [&'static str];
+
+    /// the contracts of every validated field in declaration order
+    const CONTRACTS: &'static [FieldContract];
+
+    /// iterates over all rules of all fields in declaration order
+    fn rules() -> impl Iterator<Item = &'static RuleContract> {
+        Self::CONTRACTS.iter().flat_map(|f| f.rules.iter())
+    }
+
+    /// gets the contract of the field with the given name
+    fn field_contract(name: &str) -> Option<&'static FieldContract> {
+        Self::CONTRACTS.iter().find(|f| f.field == name)
+    }
+}
+
+impl CmpOp {
+    /// the operator as it is written in a contract, e.g. `<=`
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            CmpOp::Lt => "<",
+            CmpOp::Le => "<=",
+            CmpOp::Gt => ">",
+            CmpOp::Ge => ">=",
+        }
+    }
+
+    /// true for `<` and `>`
+    pub fn is_strict(&self) -> bool {
+        matches!(self, CmpOp::Lt | CmpOp::Gt)
+    }
+
+    /// true if the right hand side is an upper bound, i.e. for `<` and `<=`
+    pub fn is_upper_bound(&self) -> bool {
+        matches!(self, CmpOp::Lt | CmpOp::Le)
+    }
+
+    /// evaluates `left op right`
+    pub fn eval<T: PartialOrd>(&self, left: T, right: T) -> bool {
+        match self {
+            CmpOp::Lt => left < right,
+            CmpOp::Le => left <= right,
+            CmpOp::Gt => left > right,
+            CmpOp::Ge => left >= right,
+        }
+    }
+}
+
+impl Display for CmpOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+impl FieldContract {
+    /// true if the field type is a primitive integer type
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self.ty,
+            "i8" | "i16"
+                | "i32"
+                | "i64"
+                | "i128"
+                | "isize"
+                | "u8"
+                | "u16"
+                | "u32"
+                | "u64"
+                | "u128"
+                | "usize"
+        )
+    }
+}
+
+impl Display for RuleContract {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_fixtures::DistillationColumn;
+    use crate::*;
+
+    #[test]
+    fn contracts_describe_all_rules() {
+        assert_eq!(DistillationColumn::TYPE_NAME, "DistillationColumn");
+        assert_eq!(DistillationColumn::CONTRACTS.len(), 3);
+        assert_eq!(DistillationColumn::rules().count(), 5);
+
+        let feed = DistillationColumn::field_contract("feed_place").unwrap();
+        assert_eq!(feed.ty, "i32");
+        assert!(feed.is_integer());
+        assert_eq!(feed.rules[0].id, "feed_place < trays");
+        assert_eq!(feed.rules[0].op, CmpOp::Lt);
+        assert_eq!(feed.rules[0].sibling, Some("trays"));
+        assert_eq!(feed.rules[0].bound, None);
+        assert_eq!(feed.rules[1].bound, Some(1.0));
+
+        let d2f = DistillationColumn::field_contract("distiliate_to_feed_ratio").unwrap();
+        assert_eq!(d2f.rules[1].rhs, "1.0");
+        assert_eq!(d2f.rules[1].bound, Some(1.0));
+        assert!(!d2f.is_integer());
+    }
+}
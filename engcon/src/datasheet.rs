@@ -0,0 +1,378 @@
+//! Generation of human-readable datasheets of the contracts of a type.
+//!
+//! A datasheet is a table of the validated fields with their type and allowed range in
+//! mathematical notation, e.g. `1 ≤ feed_place < trays`. A column with the units given by
+//! `#[unit("bar")]` is added if any field has a unit. It can be rendered as Markdown, LaTeX or
+//! HTML to attach it to design reviews.
+
+use crate::{CmpOp, Contracts, FieldContract};
+
+/// The output format of a [Datasheet]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatasheetFormat {
+    Markdown,
+    Latex,
+    Html,
+}
+
+/// A datasheet of the contracts of a type, see [Datasheet::of]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Datasheet {
+    title: String,
+    rows: Vec<DatasheetRow>,
+}
+
+/// A row of a [Datasheet] describing a single field
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatasheetRow {
+    pub field: String,
+    pub ty: String,
+    /// the unit given by `#[unit("bar")]`
+    pub unit: Option<String>,
+    /// lower bounds as right hand side and strictness
+    pub lower: Vec<(String, bool)>,
+    /// upper bounds as right hand side and strictness
+    pub upper: Vec<(String, bool)>,
+}
+
+impl Datasheet {
+    /// Generates the datasheet from the contracts of `T`
+    pub fn of<T: Contracts>() -> Self {
+        Datasheet {
+            title: T::TYPE_NAME.to_owned(),
+            rows: T::CONTRACTS.iter().map(DatasheetRow::from).collect(),
+        }
+    }
+
+    /// the title of the datasheet, the type name by default
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// sets another title, e.g. `Distillation Column Design Limits`
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// the rows of the datasheet in declaration order of the fields
+    pub fn rows(&self) -> &[DatasheetRow] {
+        &self.rows
+    }
+
+    /// renders the datasheet in the given format
+    pub fn render(&self, format: DatasheetFormat) -> String {
+        match format {
+            DatasheetFormat::Markdown => self.to_markdown(),
+            DatasheetFormat::Latex => self.to_latex(),
+            DatasheetFormat::Html => self.to_html(),
+        }
+    }
+
+    /// true if any field has a unit, the unit column is omitted otherwise
+    pub fn has_units(&self) -> bool {
+        self.rows.iter().any(|r| r.unit.is_some())
+    }
+
+    /// renders the datasheet as Markdown table
+    pub fn to_markdown(&self) -> String {
+        let units = self.has_units();
+        let mut out = format!("## {}\n\n", self.title);
+        if units {
+            out.push_str("| Field | Type | Unit | Allowed range |\n");
+            out.push_str("|---|---|---|---|\n");
+        } else {
+            out.push_str("| Field | Type | Allowed range |\n");
+            out.push_str("|---|---|---|\n");
+        }
+        for row in &self.rows {
+            let unit = match (units, &row.unit) {
+                (true, unit) => format!(" {} |", unit.as_deref().unwrap_or("")),
+                (false, _) => String::new(),
+            };
+            out.push_str(&format!(
+                "| `{}` | `{}` |{} {} |\n",
+                row.field,
+                row.ty,
+                unit,
+                row.range(DatasheetFormat::Markdown)
+            ));
+        }
+        out
+    }
+
+    /// renders the datasheet as LaTeX table
+    pub fn to_latex(&self) -> String {
+        let mut out = String::from("\\begin{table}[h]\n\\centering\n");
+        out.push_str(&format!("\\caption{{{}}}\n", latex_escape(&self.title)));
+        let units = self.has_units();
+        if units {
+            out.push_str("\\begin{tabular}{llll}\n\\hline\n");
+            out.push_str("Field & Type & Unit & Allowed range \\\\\n\\hline\n");
+        } else {
+            out.push_str("\\begin{tabular}{lll}\n\\hline\n");
+            out.push_str("Field & Type & Allowed range \\\\\n\\hline\n");
+        }
+        for row in &self.rows {
+            let unit = match (units, &row.unit) {
+                (true, unit) => format!(" {} &", latex_escape(unit.as_deref().unwrap_or(""))),
+                (false, _) => String::new(),
+            };
+            out.push_str(&format!(
+                "\\texttt{{{}}} & \\texttt{{{}}} &{} ${}$ \\\\\n",
+                latex_escape(&row.field),
+                latex_escape(&row.ty),
+                unit,
+                row.range(DatasheetFormat::Latex)
+            ));
+        }
+        out.push_str("\\hline\n\\end{tabular}\n\\end{table}\n");
+        out
+    }
+
+    /// renders the datasheet as HTML table
+    pub fn to_html(&self) -> String {
+        let mut out = String::from("<table>\n");
+        out.push_str(&format!(
+            "<caption>{}</caption>\n",
+            html_escape(&self.title)
+        ));
+        let units = self.has_units();
+        let unit_header = if units { "<th>Unit</th>" } else { "" };
+        out.push_str(&format!(
+            "<thead><tr><th>Field</th><th>Type</th>{}<th>Allowed range</th></tr></thead>\n",
+            unit_header
+        ));
+        out.push_str("<tbody>\n");
+        for row in &self.rows {
+            let unit = match (units, &row.unit) {
+                (true, unit) => format!("<td>{}</td>", html_escape(unit.as_deref().unwrap_or(""))),
+                (false, _) => String::new(),
+            };
+            out.push_str(&format!(
+                "<tr><td><code>{}</code></td><td><code>{}</code></td>{}<td>{}</td></tr>\n",
+                html_escape(&row.field),
+                html_escape(&row.ty),
+                unit,
+                row.range(DatasheetFormat::Html)
+            ));
+        }
+        out.push_str("</tbody>\n</table>\n");
+        out
+    }
+}
+
+impl From<&FieldContract> for DatasheetRow {
+    fn from(contract: &FieldContract) -> Self {
+        let mut row = DatasheetRow {
+            field: contract.field.to_owned(),
+            ty: contract.ty.to_owned(),
+            unit: contract.unit.map(str::to_owned),
+            lower: vec![],
+            upper: vec![],
+        };
+        for rule in contract.rules {
+            let bound = (rule.rhs.to_owned(), rule.op.is_strict());
+            match rule.op {
+                CmpOp::Lt | CmpOp::Le => row.upper.push(bound),
+                CmpOp::Gt | CmpOp::Ge => row.lower.push(bound),
+            }
+        }
+        row
+    }
+}
+
+impl DatasheetRow {
+    /// the allowed range in mathematical notation, e.g. `1 ≤ feed_place < trays`
+    pub fn range(&self, format: DatasheetFormat) -> String {
+        let var = |s: &str| match format {
+            DatasheetFormat::Markdown => s.to_owned(),
+            DatasheetFormat::Latex => format!("\\mathit{{{}}}", latex_escape(s)),
+            DatasheetFormat::Html => format!("<var>{}</var>", html_escape(s)),
+        };
+        // a bound of a quantity carries its unit, e.g. `1.0 bar`
+        let term = |s: &str| {
+            if !s.starts_with(|c: char| c.is_ascii_digit() || c == '-') {
+                return var(s);
+            }
+            match (format, s.split_once(' ')) {
+                (DatasheetFormat::Latex, Some((number, unit))) => {
+                    format!("{}\\,\\mathrm{{{}}}", number, latex_escape(unit))
+                }
+                (DatasheetFormat::Html, Some((number, unit))) => {
+                    format!("{} {}", number, html_escape(unit))
+                }
+                _ => s.to_owned(),
+            }
+        };
+        let less = |strict: bool| match (format, strict) {
+            (DatasheetFormat::Html, true) => "&lt;",
+            (_, true) => "<",
+            (DatasheetFormat::Markdown, false) => "≤",
+            (DatasheetFormat::Latex, false) => "\\leq",
+            (DatasheetFormat::Html, false) => "&le;",
+        };
+        let greater = |strict: bool| match (format, strict) {
+            (DatasheetFormat::Html, true) => "&gt;",
+            (_, true) => ">",
+            (DatasheetFormat::Markdown, false) => "≥",
+            (DatasheetFormat::Latex, false) => "\\geq",
+            (DatasheetFormat::Html, false) => "&ge;",
+        };
+
+        // several lower or upper bounds are written as separate relations
+        let mut relations = vec![];
+        let field = var(&self.field);
+        let mut lower = self.lower.iter();
+        let mut upper = self.upper.iter();
+        match (lower.next(), upper.next()) {
+            (Some((lo, lo_strict)), Some((up, up_strict))) => relations.push(format!(
+                "{} {} {} {} {}",
+                term(lo),
+                less(*lo_strict),
+                field,
+                less(*up_strict),
+                term(up)
+            )),
+            (Some((lo, strict)), None) => {
+                relations.push(format!("{} {} {}", field, greater(*strict), term(lo)))
+            }
+            (None, Some((up, strict))) => {
+                relations.push(format!("{} {} {}", field, less(*strict), term(up)))
+            }
+            (None, None) => {}
+        }
+        relations.extend(
+            lower.map(|(lo, strict)| format!("{} {} {}", field, greater(*strict), term(lo))),
+        );
+        relations
+            .extend(upper.map(|(up, strict)| format!("{} {} {}", field, less(*strict), term(up))));
+
+        let sep = match format {
+            DatasheetFormat::Latex => ",\\ ",
+            _ => ", ",
+        };
+        relations.join(sep)
+    }
+}
+
+/// escapes the special characters of LaTeX, character by character as the replacements
+/// contain braces themselves
+fn latex_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\textbackslash{}"),
+            '~' => out.push_str("\\textasciitilde{}"),
+            '^' => out.push_str("\\textasciicircum{}"),
+            '_' | '&' | '%' | '#' | '$' | '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::DistillationColumn;
+    use crate::*;
+
+    #[test]
+    fn ranges_use_mathematical_notation() {
+        let sheet = Datasheet::of::<DistillationColumn>();
+        let ranges: Vec<String> = sheet
+            .rows()
+            .iter()
+            .map(|r| r.range(DatasheetFormat::Markdown))
+            .collect();
+        assert_eq!(
+            ranges,
+            [
+                "trays ≥ 3",
+                "1 ≤ feed_place < trays",
+                "0.0 < distiliate_to_feed_ratio < 1.0"
+            ]
+        );
+    }
+
+    #[test]
+    fn all_formats_contain_every_field() {
+        let sheet = Datasheet::of::<DistillationColumn>().with_title("Column Limits");
+        let md = sheet.render(DatasheetFormat::Markdown);
+        assert!(md.starts_with("## Column Limits"));
+        assert!(md.contains("| `feed_place` | `i32` | 1 ≤ feed_place < trays |"));
+
+        let tex = sheet.render(DatasheetFormat::Latex);
+        assert!(tex.contains("$1 \\leq \\mathit{feed\\_place} < \\mathit{trays}$"));
+
+        let html = sheet.render(DatasheetFormat::Html);
+        assert!(html.contains("1 &le; <var>feed_place</var> &lt; <var>trays</var>"));
+    }
+
+    #[test]
+    fn latex_special_characters_are_escaped() {
+        assert_eq!(
+            latex_escape("C_2H_6 {5% & #1} $^~\\"),
+            "C\\_2H\\_6 \\{5\\% \\& \\#1\\} \\$\\textasciicircum{}\\textasciitilde{}\\textbackslash{}"
+        );
+    }
+
+    #[derive(Debug, Clone, PartialEq, engcon_macros::Validatable)]
+    struct Vessel {
+        #[unit("bar")]
+        #[validate_value(x > 0.0, x <= 10.0)]
+        pressure: f64,
+        #[validate_value(x >= 1)]
+        nozzles: u8,
+    }
+
+    #[test]
+    fn units_are_shown_in_a_column() {
+        let sheet = Datasheet::of::<Vessel>();
+        assert_eq!(sheet.rows()[0].unit.as_deref(), Some("bar"));
+
+        let md = sheet.to_markdown();
+        assert!(md.contains("| Field | Type | Unit | Allowed range |"));
+        assert!(md.contains("| `pressure` | `f64` | bar | 0.0 < pressure ≤ 10.0 |"));
+        assert!(md.contains("| `nozzles` | `u8` |  | nozzles ≥ 1 |"));
+        assert!(sheet.to_html().contains("<td>bar</td>"));
+        assert!(sheet.to_latex().contains("& bar & $"));
+
+        let err = Vessel {
+            pressure: 12.0,
+            nozzles: 2,
+        }
+        .validate()
+        .unwrap_err();
+        assert_eq!(err.msg(), "value=12 bar: 'pressure' <= '10.0 bar'");
+    }
+
+    #[test]
+    fn units_of_bounds_are_upright_in_latex() {
+        let row = DatasheetRow {
+            field: "design_pressure".to_owned(),
+            ty: "Pressure".to_owned(),
+            unit: None,
+            lower: vec![("1.0 bar".to_owned(), true)],
+            upper: vec![("max_pressure".to_owned(), false)],
+        };
+        assert_eq!(
+            row.range(DatasheetFormat::Latex),
+            "1.0\\,\\mathrm{bar} < \\mathit{design\\_pressure} \\leq \\mathit{max\\_pressure}"
+        );
+        assert_eq!(
+            row.range(DatasheetFormat::Markdown),
+            "1.0 bar < design_pressure ≤ max_pressure"
+        );
+    }
+}
@@ -20,6 +20,28 @@
 //! This pattern is also used by by the well known [strum crate](https://docs.rs/strum/latest/strum/) that has helpful procedural macros
 //! for enumerations.
 //!
+//! # Optional Features
+//!
+//! - `csv`: validates every row of a CSV into [Validated] records and an error table, see
+//!   [tabular].
+//! - `derive`: re-exports the derive macros of engcon_macros.
+//! - `json-schema`: exports the contracts of a type as JSON Schema, see [schema].
+//! - `proptest`: generates valid and near-boundary invalid values for property tests, see
+//!   [strategy].
+//! - `rand`: draws random valid instances with a seedable random number generator, see
+//!   [UniformSampler].
+//! - `rayon`: validates many values in parallel with [validate_many] and
+//!   [try_into_validated_par].
+//! - `rules`: loads contracts at runtime from TOML or JSON rule files and validates any
+//!   `Serialize` value, see [rules].
+//! - `serde`: implements `Serialize` for [ValidationError] and [ValidationReport] and adds
+//!   [write_json_lines].
+//! - `units`: converts values between common engineering units like °C, K, psi and bar, see
+//!   [units].
+//! - `uom`: re-exports [uom](https://docs.rs/uom), its quantities like `Pressure` can be
+//!   validated with rules like `x > 1.0 bar`.
+//! - `yaml`: loads rule files in the YAML format, implies `rules`.
+//!
 
 use std::{
     error::Error,
@@ -27,16 +49,75 @@ use std::{
     ops::{Deref, DerefMut},
 };
 
+// the generated code refers to `::engcon`, also in the tests of this crate
+extern crate self as engcon;
+
 #[cfg(feature = "derive")]
 pub use engcon_macros::*;
 
+#[cfg(feature = "uom")]
+pub use uom;
+
+mod contracts;
+pub use contracts::*;
+
+pub mod datasheet;
+
+pub mod types;
+
+mod report;
+pub use report::*;
+
+mod optimize;
+pub use optimize::*;
+
+mod repair;
+pub use repair::*;
+
+mod sampling;
+pub use sampling::*;
+
+mod boundary;
+pub use boundary::*;
+
+mod composition;
+pub use composition::*;
+
+mod stats;
+pub use stats::*;
+
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "rayon")]
+pub use parallel::*;
+
+#[cfg(feature = "json-schema")]
+pub mod schema;
+
+#[cfg(feature = "proptest")]
+pub mod strategy;
+
+#[cfg(feature = "rules")]
+pub mod rules;
+
+#[cfg(feature = "csv")]
+pub mod tabular;
+
+#[cfg(feature = "units")]
+pub mod units;
+
+#[cfg(test)]
+pub(crate) mod test_fixtures;
+
 /// A new-type  that ensures validated data for a generic T.
 ///
 /// Use the [Validatable] dervice macro and it's rules to
 /// implement define validation rules on a type T.
 ///
-/// The type parameter `T` must be [Sized] and implement the [Validator] trait.
-pub struct Validated<T: Validator + Sized> {
+/// The type parameter `T` must be [Sized] and is constructed either by the [Validator] trait
+/// or by [Validated::try_new_with] if the contracts depend on a context.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Validated<T: Sized> {
     inner: T,
 }
 
@@ -45,6 +126,27 @@ pub struct Validated<T: Validator + Sized> {
 pub struct ValidationError {
     msg: String,
     src: String,
+    severity: Severity,
+    violation: Option<Box<RuleViolation>>,
+}
+
+/// Details of a violated rule, attached to a [ValidationError] by the derive macro
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleViolation {
+    /// the path of the field, e.g. `feed_place`
+    pub field: String,
+    /// the id of the violated rule, see [RuleContract::id]
+    pub rule: String,
+    /// the comparison operator of the rule
+    pub op: CmpOp,
+    /// the actual value of the right hand side, e.g. the value of `trays` for `x < trays`
+    pub bound: String,
+    /// the actual value of the field
+    pub value: String,
+    /// the bound as number if the field has a primitive numeric type, e.g. to plot violations
+    pub bound_num: Option<f64>,
+    /// the value as number if the field has a primitive numeric type
+    pub value_num: Option<f64>,
 }
 
 /// Provides methods to validate and to transform into a [Validated] new-type.
@@ -57,6 +159,18 @@ pub trait Validator: Sized {
     /// manual implemenation.
     fn validate(&self) -> Result<(), ValidationError>;
 
+    /// Checks every rule and collects all violations in a [ValidationReport].
+    ///
+    /// The default implementation stops at the first error, the derive macro
+    /// [Validatable] generates an implementation that checks every rule.
+    fn validate_all(&self) -> ValidationReport {
+        let mut report = ValidationReport::new(std::any::type_name::<Self>().to_owned());
+        if let Err(err) = self.validate() {
+            report.push(err);
+        }
+        report
+    }
+
     /// tries to transform Self into a [Validated] may give an [ValidationError]
     fn try_into_validated(self) -> Result<Validated<Self>, ValidationError> {
         match self.validate() {
@@ -70,11 +184,160 @@ pub trait Validator: Sized {
     }
 }
 
+/// Provides methods to validate against contracts that depend on external data of type `C`.
+///
+/// Use `#[validate(context = C)]` on a struct deriving [Validatable] and refer to the context
+/// as `ctx` in the rules, e.g. `#[validate_value(x <= ctx.max_pressure)]`.
+pub trait ValidatorWithContext<C>: Sized {
+    /// Checks if the underlying data is valid in the given context and returns an
+    /// [ValidationError] if not.
+    fn validate_with(&self, ctx: &C) -> Result<(), ValidationError>;
+
+    /// Checks every rule in the given context and collects all violations in a [ValidationReport].
+    fn validate_all_with(&self, ctx: &C) -> ValidationReport {
+        let mut report = ValidationReport::new(std::any::type_name::<Self>().to_owned());
+        if let Err(err) = self.validate_with(ctx) {
+            report.push(err);
+        }
+        report
+    }
+
+    /// tries to transform Self into a [Validated] in the given context, may give an [ValidationError]
+    fn try_into_validated_with(self, ctx: &C) -> Result<Validated<Self>, ValidationError> {
+        match self.validate_with(ctx) {
+            Ok(_) => {
+                // just checked if that is validated...
+                let reval = unsafe { Validated::new_unchecked(self) };
+                Ok(reval)
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Formats the values in the generated [ValidationError]s, by [Display] if possible and by
+/// [Debug](std::fmt::Debug) otherwise, e.g. for `uom` quantities.
+///
+/// The generated code calls `(&FormatValue(&value)).format_value()`, auto-referencing prefers
+/// [FormatDisplay] if the type implements [Display].
+#[doc(hidden)]
+pub struct FormatValue<'a, T: ?Sized>(pub &'a T);
+
+#[doc(hidden)]
+pub trait FormatDisplay {
+    fn format_value(&self) -> String;
+}
+
+#[doc(hidden)]
+pub trait FormatDebug {
+    fn format_value(&self) -> String;
+}
+
+impl<T: Display + ?Sized> FormatDisplay for FormatValue<'_, T> {
+    fn format_value(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+impl<T: std::fmt::Debug + ?Sized> FormatDebug for &FormatValue<'_, T> {
+    fn format_value(&self) -> String {
+        format!("{:?}", self.0)
+    }
+}
+
+/// Gets the rule ids of a field with nested validation for [ValidationStats], by [Contracts]
+/// if the type implements it and none otherwise, e.g. for manual [Validator] implementations.
+///
+/// The generated code calls `(&NestedRules::<T>(PhantomData)).rule_ids()`, auto-referencing
+/// prefers [NestedContracts] if `T` implements [Contracts].
+#[doc(hidden)]
+pub struct NestedRules<T>(pub std::marker::PhantomData<T>);
+
+#[doc(hidden)]
+pub trait NestedContracts {
+    fn rule_ids(&self) -> Vec<&'static str>;
+}
+
+#[doc(hidden)]
+pub trait NestedValidator {
+    fn rule_ids(&self) -> Vec<&'static str>;
+}
+
+impl<T: Contracts> NestedContracts for NestedRules<T> {
+    fn rule_ids(&self) -> Vec<&'static str> {
+        T::rules().map(|rule| rule.id).collect()
+    }
+}
+
+impl<T> NestedValidator for &NestedRules<T> {
+    fn rule_ids(&self) -> Vec<&'static str> {
+        vec![]
+    }
+}
+
 impl Error for ValidationError {}
 
 impl ValidationError {
     pub fn new(msg: String, src: String) -> Self {
-        ValidationError { msg, src }
+        ValidationError {
+            msg,
+            src,
+            severity: Severity::Error,
+            violation: None,
+        }
+    }
+
+    /// attaches the details of the violated rule
+    pub fn with_violation(mut self, violation: RuleViolation) -> Self {
+        self.violation = Some(Box::new(violation));
+        self
+    }
+
+    /// Moves the error into the field `field` of the outer type `src`, the field is prepended
+    /// to the path of the violated rule, e.g. `feed_quality.0` for a [types::Fraction].
+    pub fn nested_in(mut self, field: &str, src: &str) -> Self {
+        self.msg = format!("{}: {}", field, self.msg);
+        self.src = src.to_owned();
+        if let Some(violation) = &mut self.violation {
+            violation.field = format!("{}.{}", field, violation.field);
+            violation.rule = format!("{}.{}", field, violation.rule);
+        }
+        self
+    }
+
+    /// changes the severity, [Severity::Error] by default
+    pub fn with_severity(mut self, severity: Severity) -> Self {
+        self.severity = severity;
+        self
+    }
+
+    /// the message describing the error
+    pub fn msg(&self) -> &str {
+        &self.msg
+    }
+
+    /// the source of the error, i.e. the name of the validated type
+    pub fn src(&self) -> &str {
+        &self.src
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// the details of the violated rule if known
+    pub fn violation(&self) -> Option<&RuleViolation> {
+        self.violation.as_deref()
+    }
+
+    /// the path of the field that violates a rule if known
+    pub fn field(&self) -> Option<&str> {
+        self.violation.as_ref().map(|v| v.field.as_str())
+    }
+
+    /// the id of the violated rule if known
+    pub fn rule(&self) -> Option<&str> {
+        self.violation.as_ref().map(|v| v.rule.as_str())
     }
 }
 
@@ -84,7 +347,7 @@ impl Display for ValidationError {
     }
 }
 
-impl<T: Validator + Sized> Validated<T> {
+impl<T: Sized> Validated<T> {
     /// Generates a validated instance of T, usable for compile-time API safety.
     ///
     /// # Safety
@@ -93,6 +356,33 @@ impl<T: Validator + Sized> Validated<T> {
         Validated::<T> { inner }
     }
 
+    /// tries to generate a validated instance of T whose contracts depend on the context `ctx`
+    pub fn try_new_with<C>(inner: T, ctx: &C) -> Result<Self, ValidationError>
+    where
+        T: ValidatorWithContext<C>,
+    {
+        inner.try_into_validated_with(ctx)
+    }
+
+    /// Tries to generate a validated instance of T that fulfills its compiled contracts and
+    /// the runtime rules of an overlay, e.g. stricter site-specific limits.
+    ///
+    /// The overlay is checked against the fields of T when it is loaded, see
+    /// [RuleSet::for_type](rules::RuleSet::for_type).
+    #[cfg(feature = "rules")]
+    pub fn try_new_with_overlay(
+        inner: T,
+        overlay: &rules::TypedRuleSet<T>,
+    ) -> Result<Self, ValidationError>
+    where
+        T: Validator + serde::Serialize,
+    {
+        inner.validate()?;
+        overlay.validate(&inner)?;
+        // just checked both the contracts and the overlay...
+        Ok(unsafe { Validated::new_unchecked(inner) })
+    }
+
     /// gets the inner unchecked type
     pub fn into_inner(self) -> T {
         self.inner
@@ -126,7 +416,7 @@ impl<T: Validator + Sized> TryFrom<T> for Validated<T> {
 }
  */
 
-impl<T: Validator + Sized> Deref for Validated<T> {
+impl<T: Sized> Deref for Validated<T> {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
@@ -134,12 +424,30 @@ impl<T: Validator + Sized> Deref for Validated<T> {
     }
 }
 
-impl<T: Validator + Sized> DerefMut for Validated<T> {
+impl<T: Sized> DerefMut for Validated<T> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.inner
     }
 }
 
+/// Serializes the inner value
+#[cfg(feature = "serde")]
+impl<T: serde::Serialize> serde::Serialize for Validated<T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.inner.serialize(serializer)
+    }
+}
+
+/// Deserializes the inner value and fails if it is invalid
+#[cfg(feature = "serde")]
+impl<'de, T: serde::Deserialize<'de> + Validator> serde::Deserialize<'de> for Validated<T> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        T::deserialize(deserializer)?
+            .try_into_validated()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -180,4 +488,78 @@ mod tests {
         let result = tmp.clone().validate();
         assert!(result.is_err());
     }
+
+    struct PlantLimits {
+        max_pressure: f64,
+    }
+
+    #[derive(Debug, Clone, PartialEq, engcon_macros::Validatable)]
+    #[validate(context = PlantLimits)]
+    struct Vessel {
+        #[validate_value(x > 0.0, x <= ctx.max_pressure)]
+        design_pressure: f64,
+    }
+
+    #[test]
+    fn context_limits_are_used() {
+        let limits = PlantLimits { max_pressure: 10.0 };
+        let vessel = Vessel {
+            design_pressure: 12.0,
+        };
+        let err = vessel.validate_with(&limits).unwrap_err();
+        // primitive values are formatted by `Display`, i.e. without a trailing `.0`
+        let violation = err.violation().unwrap();
+        assert_eq!(
+            (violation.value.as_str(), violation.bound.as_str()),
+            ("12", "10")
+        );
+        assert!(vessel
+            .validate_with(&PlantLimits { max_pressure: 16.0 })
+            .is_ok());
+
+        let vessel = Validated::try_new_with(
+            Vessel {
+                design_pressure: 8.0,
+            },
+            &limits,
+        )
+        .unwrap();
+        assert_eq!(vessel.design_pressure, 8.0);
+    }
+
+    #[cfg(feature = "uom")]
+    mod quantities {
+        use crate::*;
+        use uom::si::f64::{Pressure, ThermodynamicTemperature};
+        use uom::si::pressure::{bar, pascal};
+        use uom::si::thermodynamic_temperature::kelvin;
+
+        #[derive(Debug, Clone, PartialEq, engcon_macros::Validatable)]
+        struct Vessel {
+            #[validate_value(x > 1 bar, x <= max_pressure)]
+            design_pressure: Pressure,
+            // the same type as `Pressure`, spelled differently
+            max_pressure: uom::si::f64::Pressure,
+            #[validate_value(x >= 273.15 kelvin)]
+            temperature: ThermodynamicTemperature,
+        }
+
+        #[test]
+        fn quantities_are_compared_in_their_units() {
+            let mut vessel = Vessel {
+                design_pressure: Pressure::new::<bar>(8.0),
+                max_pressure: Pressure::new::<bar>(10.0),
+                temperature: ThermodynamicTemperature::new::<kelvin>(300.0),
+            };
+            assert!(vessel.validate().is_ok());
+
+            vessel.design_pressure = Pressure::new::<pascal>(50_000.0);
+            let err = vessel.validate().unwrap_err();
+            assert_eq!(err.rule(), Some("design_pressure > 1.0 bar"));
+
+            let rule = &Vessel::CONTRACTS[0].rules[0];
+            assert_eq!(rule.rhs, "1.0 bar");
+            assert_eq!(rule.bound, None);
+        }
+    }
 }
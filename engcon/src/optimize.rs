@@ -0,0 +1,279 @@
+//! Helpers to use the contracts in numeric optimization, e.g. as penalty terms.
+
+use crate::{CmpOp, Contracts, RuleContract};
+
+/// Scales the violations of the rules into a penalty term for an optimizer.
+///
+/// The penalty is `weight * sum(violation^exponent)`, i.e. an exponent of 2 gives a
+/// quadratic penalty.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Penalty {
+    pub weight: f64,
+    pub exponent: f64,
+}
+
+/// The slack of a rule, i.e. how far the value is away from the bound of the rule.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Margin {
+    /// the rule the margin belongs to
+    pub rule: &'static RuleContract,
+    /// the absolute slack, negative if the rule is violated
+    pub absolute: f64,
+    /// the slack relative to the bound, equals the absolute slack for a bound of 0
+    pub slack: f64,
+}
+
+impl Margin {
+    /// generates the margin of a rule given the absolute slack and the bound
+    pub fn new(rule: &'static RuleContract, absolute: f64, bound: f64) -> Self {
+        let slack = if bound == 0.0 {
+            absolute
+        } else {
+            absolute / bound.abs()
+        };
+        Margin {
+            rule,
+            absolute,
+            slack,
+        }
+    }
+
+    /// true if the relative slack is below the threshold
+    pub fn is_active(&self, threshold: f64) -> bool {
+        self.slack < threshold
+    }
+
+    /// keeps the margins whose relative slack is below the threshold, the smallest first
+    pub fn below(margins: impl IntoIterator<Item = Margin>, threshold: f64) -> Vec<Margin> {
+        let mut active: Vec<Margin> = margins
+            .into_iter()
+            .filter(|m| m.is_active(threshold))
+            .collect();
+        active.sort_by(|a, b| a.slack.total_cmp(&b.slack));
+        active
+    }
+}
+
+/// The contracts of a type as input for numeric solvers, see [LinearConstraints::of].
+///
+/// Constant rules like `x >= 3` give the box bounds `lower <= x <= upper` of a variable.
+/// Cross-field rules like `feed_place < trays` give the rows of the sparse matrix `A` in
+/// `A x <= b`. Strict rules on integer fields are tightened by 1, e.g. `x > 2` gives a lower
+/// bound of 3, strict rules on float fields are returned as closed bounds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearConstraints {
+    /// the name of the variable at each index
+    pub fields: Vec<&'static str>,
+    /// the lower bound of each variable, `-inf` if unbounded
+    pub lower: Vec<f64>,
+    /// the upper bound of each variable, `inf` if unbounded
+    pub upper: Vec<f64>,
+    /// the non-zero entries of `A` as `(row, column, value)`
+    pub a: Vec<(usize, usize, f64)>,
+    /// the right hand side of each row
+    pub b: Vec<f64>,
+    /// the rule each row was generated from
+    pub row_rules: Vec<&'static RuleContract>,
+    /// the rules that are neither constant nor linear in the fields, e.g. `x < ctx.max_pressure`
+    pub unsupported: Vec<&'static RuleContract>,
+}
+
+impl LinearConstraints {
+    /// extracts the box bounds and linear constraints from the contracts of `T`
+    pub fn of<T: Contracts>() -> Self {
+        let mut lc = LinearConstraints {
+            fields: T::CONTRACTS.iter().map(|f| f.field).collect(),
+            lower: vec![f64::NEG_INFINITY; T::CONTRACTS.len()],
+            upper: vec![f64::INFINITY; T::CONTRACTS.len()],
+            a: vec![],
+            b: vec![],
+            row_rules: vec![],
+            unsupported: vec![],
+        };
+
+        for (col, field) in T::CONTRACTS.iter().enumerate() {
+            let shift = if field.is_integer() { 1.0 } else { 0.0 };
+            for rule in field.rules {
+                let strict_shift = if rule.op.is_strict() { shift } else { 0.0 };
+                match (rule.bound, rule.sibling) {
+                    (Some(bound), _) => match rule.op {
+                        CmpOp::Lt | CmpOp::Le => {
+                            lc.upper[col] = lc.upper[col].min(bound - strict_shift)
+                        }
+                        CmpOp::Gt | CmpOp::Ge => {
+                            lc.lower[col] = lc.lower[col].max(bound + strict_shift)
+                        }
+                    },
+                    (None, Some(sibling)) => {
+                        let other = lc.index_or_insert(sibling);
+                        // `x < y` gives `x - y <= 0` and `x > y` gives `y - x <= 0`
+                        let (below, above) = if rule.op.is_upper_bound() {
+                            (col, other)
+                        } else {
+                            (other, col)
+                        };
+                        let row = lc.b.len();
+                        lc.a.push((row, below, 1.0));
+                        lc.a.push((row, above, -1.0));
+                        lc.b.push(-strict_shift);
+                        lc.row_rules.push(rule);
+                    }
+                    (None, None) => lc.unsupported.push(rule),
+                }
+            }
+        }
+        lc
+    }
+
+    /// the index of the variable with the given field name
+    pub fn index_of(&self, field: &str) -> Option<usize> {
+        self.fields.iter().position(|f| *f == field)
+    }
+
+    /// the box bounds `(lower, upper)` of the variable with the given field name
+    pub fn bounds(&self, field: &str) -> Option<(f64, f64)> {
+        self.index_of(field).map(|i| (self.lower[i], self.upper[i]))
+    }
+
+    /// the number of rows of `A`
+    pub fn rows(&self) -> usize {
+        self.b.len()
+    }
+
+    /// the matrix `A` as dense rows, useful for small problems or solvers without sparse input
+    pub fn dense_a(&self) -> Vec<Vec<f64>> {
+        let mut dense = vec![vec![0.0; self.fields.len()]; self.rows()];
+        for &(row, col, value) in &self.a {
+            dense[row][col] += value;
+        }
+        dense
+    }
+
+    /// fields that are referenced by a rule but have no contract are appended as unbounded variable
+    fn index_or_insert(&mut self, field: &'static str) -> usize {
+        match self.index_of(field) {
+            Some(i) => i,
+            None => {
+                self.fields.push(field);
+                self.lower.push(f64::NEG_INFINITY);
+                self.upper.push(f64::INFINITY);
+                self.fields.len() - 1
+            }
+        }
+    }
+}
+
+impl Penalty {
+    pub fn new(weight: f64, exponent: f64) -> Self {
+        Penalty { weight, exponent }
+    }
+
+    /// a linear penalty with the given weight
+    pub fn linear(weight: f64) -> Self {
+        Penalty::new(weight, 1.0)
+    }
+
+    /// a quadratic penalty with the given weight
+    pub fn quadratic(weight: f64) -> Self {
+        Penalty::new(weight, 2.0)
+    }
+
+    /// applies the penalty on the violations of the rules
+    pub fn apply(&self, violations: &[f64]) -> f64 {
+        self.weight
+            * violations
+                .iter()
+                .map(|v| v.powf(self.exponent))
+                .sum::<f64>()
+    }
+}
+
+impl Default for Penalty {
+    fn default() -> Self {
+        Penalty::linear(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_fixtures::DistillationColumn;
+    use crate::*;
+
+    #[test]
+    fn valid_designs_have_no_violation() {
+        let dc = DistillationColumn {
+            trays: 20,
+            feed_place: 10,
+            distiliate_to_feed_ratio: 0.5,
+        };
+        assert_eq!(dc.violations(), vec![0.0; 5]);
+        assert_eq!(dc.violation(), 0.0);
+    }
+
+    #[test]
+    fn violations_are_distances_to_the_bound() {
+        let dc = DistillationColumn {
+            trays: 2,
+            feed_place: 25,
+            distiliate_to_feed_ratio: 1.5,
+        };
+        let v = dc.violations();
+        assert_eq!(v[0], 1.0);
+        // feed_place - trays + 1
+        assert_eq!(v[1], 24.0);
+        assert_eq!(v[2], 0.0);
+        assert_eq!(v[3], 0.0);
+        assert!((v[4] - 0.5).abs() < 1e-6);
+
+        // a value on a strict bound is violated by the smallest step
+        let on_bound = DistillationColumn {
+            distiliate_to_feed_ratio: 1.0,
+            ..dc
+        };
+        assert!(on_bound.violations()[4] > 0.0);
+
+        let penalty = Penalty::quadratic(10.0);
+        assert!((dc.penalty(&penalty) - 10.0 * (1.0 + 576.0 + 0.25)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn margins_are_relative_to_the_bound() {
+        let dc = DistillationColumn {
+            trays: 20,
+            feed_place: 19,
+            distiliate_to_feed_ratio: 0.99,
+        };
+        let margins = dc.margins();
+        assert_eq!(margins.len(), 5);
+        assert_eq!(margins[1].rule.id, "feed_place < trays");
+        assert_eq!(margins[1].absolute, 1.0);
+        assert_eq!(margins[1].slack, 0.05);
+        // a bound of 0 gives the absolute slack
+        assert!((margins[3].slack - 0.99).abs() < 1e-6);
+
+        let active: Vec<_> = dc.active_rules(0.06).iter().map(|m| m.rule.id).collect();
+        assert_eq!(
+            active,
+            ["distiliate_to_feed_ratio < 1.0", "feed_place < trays"]
+        );
+    }
+
+    #[test]
+    fn bounds_and_linear_rows_are_extracted() {
+        let lc = LinearConstraints::of::<DistillationColumn>();
+        assert_eq!(
+            lc.fields,
+            ["trays", "feed_place", "distiliate_to_feed_ratio"]
+        );
+        assert_eq!(lc.bounds("trays"), Some((3.0, f64::INFINITY)));
+        assert_eq!(lc.bounds("feed_place"), Some((1.0, f64::INFINITY)));
+        assert_eq!(lc.bounds("distiliate_to_feed_ratio"), Some((0.0, 1.0)));
+
+        // feed_place - trays <= -1
+        assert_eq!(lc.rows(), 1);
+        assert_eq!(lc.dense_a(), [[-1.0, 1.0, 0.0]]);
+        assert_eq!(lc.b, [-1.0]);
+        assert_eq!(lc.row_rules[0].id, "feed_place < trays");
+        assert!(lc.unsupported.is_empty());
+    }
+}
@@ -0,0 +1,113 @@
+//! Parallel batch validation with [rayon](https://docs.rs/rayon), e.g. of large scenario sets.
+
+use rayon::prelude::*;
+
+use crate::{Validated, ValidationError, ValidationReport, ValidationStats, Validator};
+
+/// Checks every rule of every item in parallel.
+///
+/// The reports are in the order of the items, the statistics count the failures per rule and
+/// infer the passes, see [ValidationStats::record].
+///
+/// ```
+/// use engcon::*;
+/// use engcon_macros::Validatable;
+///
+/// #[derive(Debug, Validatable)]
+/// struct DistillationColumn {
+///     #[validate_value(x >= 3)]
+///     trays: i32,
+///     #[validate_value(x < trays, x >= 1)]
+///     feed_place: i32,
+/// }
+///
+/// let columns: Vec<_> = (1..=10)
+///     .map(|feed_place| DistillationColumn { trays: 6, feed_place })
+///     .collect();
+/// let (reports, stats) = validate_many(&columns);
+/// assert!(reports[0].is_valid());
+/// assert_eq!(stats.failures("feed_place < trays"), 5);
+/// assert_eq!(stats.most_common(1), [("feed_place < trays", 5)]);
+/// assert_eq!(stats.failure_rate("feed_place < trays"), 0.5);
+/// ```
+pub fn validate_many<T: Validator + Sync>(items: &[T]) -> (Vec<ValidationReport>, ValidationStats) {
+    let reports: Vec<_> = items.par_iter().map(Validator::validate_all).collect();
+    let stats = reports.iter().collect();
+    (reports, stats)
+}
+
+/// Tries to transform every item into a [Validated] in parallel.
+///
+/// The results are in the order of the items, an invalid item gives its first error as in
+/// [Validator::try_into_validated]. The statistics count all failures per rule like
+/// [validate_many].
+pub fn try_into_validated_par<T, I>(
+    items: I,
+) -> (Vec<Result<Validated<T>, ValidationError>>, ValidationStats)
+where
+    T: Validator + Send,
+    I: IntoIterator<Item = T>,
+{
+    let items: Vec<T> = items.into_iter().collect();
+    let checked: Vec<_> = items
+        .into_par_iter()
+        .map(|item| {
+            let report = item.validate_all();
+            (item, report)
+        })
+        .collect();
+
+    let stats = checked.iter().map(|(_, report)| report).collect();
+    let results = checked
+        .into_iter()
+        .map(|(item, report)| {
+            report.into_result()?;
+            // just checked if that is validated...
+            Ok(unsafe { Validated::new_unchecked(item) })
+        })
+        .collect();
+    (results, stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_fixtures::DistillationColumn;
+    use crate::*;
+
+    #[test]
+    fn results_keep_the_input_order() {
+        let columns = (0..1000).map(|i| DistillationColumn::new(10, i % 20));
+        let (results, stats) = try_into_validated_par(columns);
+
+        assert_eq!(results.len(), 1000);
+        for (i, result) in results.iter().enumerate() {
+            let feed_place = i as i32 % 20;
+            match result {
+                Ok(column) => assert_eq!(column.feed_place, feed_place),
+                Err(_) => assert!(!(1..10).contains(&feed_place)),
+            }
+        }
+        assert_eq!(stats.invalid(), 550);
+        assert_eq!(stats.failures("feed_place < trays"), 500);
+        assert_eq!(stats.failures("feed_place >= 1"), 50);
+        assert_eq!(stats.failure_rate("feed_place < trays"), 0.5);
+        assert_eq!(stats.failure_rate("feed_place >= 1"), 0.05);
+    }
+
+    #[test]
+    fn failure_rates_count_the_valid_items() {
+        let columns: Vec<_> = (0..8).map(|i| DistillationColumn::new(4, i)).collect();
+        let (reports, stats) = validate_many(&columns);
+
+        assert_eq!(reports.iter().filter(|r| r.is_valid()).count(), 3);
+        assert_eq!(
+            stats.rule("feed_place < trays"),
+            RuleCounts {
+                passed: 4,
+                failed: 4
+            }
+        );
+        assert_eq!(stats.failure_rate("feed_place < trays"), 0.5);
+        assert_eq!(stats.failure_rate("feed_place >= 1"), 0.125);
+    }
+}
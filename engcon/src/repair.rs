@@ -0,0 +1,106 @@
+//! Repair of invalid values by clamping them into the feasible region.
+
+/// A field that was changed by a generated `repair()` method
+#[derive(Debug, Clone, PartialEq)]
+pub struct RepairChange {
+    pub field: &'static str,
+    /// the value before the repair
+    pub old: String,
+    /// the value after the repair
+    pub new: String,
+}
+
+/// Lists every field that was changed by a generated `repair()` method.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RepairReport {
+    /// the changed fields in the order they were repaired
+    pub changes: Vec<RepairChange>,
+    /// fields whose rules contradict each other for the current values, e.g. `feed_place`
+    /// with `x < trays, x >= 1` and `trays = 1`
+    pub unresolved: Vec<&'static str>,
+}
+
+impl RepairReport {
+    /// true if no field was changed
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// true if every field could be repaired
+    pub fn is_resolved(&self) -> bool {
+        self.unresolved.is_empty()
+    }
+
+    /// true if the field with the given name was changed
+    pub fn changed(&self, field: &str) -> bool {
+        self.changes.iter().any(|c| c.field == field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_fixtures::DistillationColumn;
+    use crate::*;
+
+    #[test]
+    fn siblings_are_repaired_first() {
+        let mut dc = DistillationColumn {
+            feed_place: 5,
+            trays: 2,
+            distiliate_to_feed_ratio: 1.2,
+        };
+        let report = dc.repair();
+        assert!(report.is_resolved());
+        assert_eq!(dc.trays, 3);
+        assert_eq!(dc.feed_place, 2);
+        assert!(dc.distiliate_to_feed_ratio < 1.0);
+        assert!(dc.validate().is_ok());
+
+        let fields: Vec<_> = report.changes.iter().map(|c| c.field).collect();
+        assert_eq!(fields, ["trays", "feed_place", "distiliate_to_feed_ratio"]);
+        assert_eq!(report.changes[0].old, "2");
+        assert_eq!(report.changes[0].new, "3");
+    }
+
+    #[test]
+    fn epsilon_keeps_distance_to_strict_bounds() {
+        let mut dc = DistillationColumn {
+            feed_place: 1,
+            trays: 10,
+            distiliate_to_feed_ratio: -0.5,
+        };
+        let report = dc.repair_with(0.01);
+        assert_eq!(report.changes.len(), 1);
+        assert!(report.changed("distiliate_to_feed_ratio"));
+        assert!((dc.distiliate_to_feed_ratio - 0.01).abs() < 1e-6);
+
+        assert!(dc.repair().is_empty());
+    }
+
+    #[test]
+    fn nan_is_unresolved() {
+        let mut dc = DistillationColumn {
+            distiliate_to_feed_ratio: f32::NAN,
+            ..DistillationColumn::new(10, 5)
+        };
+        let report = dc.repair();
+        assert!(report.is_empty());
+        assert_eq!(report.unresolved, ["distiliate_to_feed_ratio"]);
+    }
+
+    #[derive(Debug, Clone, engcon_macros::Validatable)]
+    struct Counter {
+        // the bound is no `f64`, it rounds down to 2^53
+        #[validate_value(x >= 9_007_199_254_740_993)]
+        count: i64,
+    }
+
+    #[test]
+    fn rounded_values_are_stepped_to_the_valid_side() {
+        let mut counter = Counter { count: 0 };
+        let report = counter.repair();
+        assert!(report.is_resolved());
+        assert_eq!(counter.count, 9_007_199_254_740_993);
+        assert!(counter.validate().is_ok());
+    }
+}
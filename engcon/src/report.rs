@@ -0,0 +1,233 @@
+//! Collection of all violated rules of a validation, see [Validator::validate_all](crate::Validator::validate_all).
+
+use std::fmt::Display;
+
+use crate::ValidationError;
+
+/// The severity of a [ValidationError]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Severity {
+    #[default]
+    Error,
+    Warning,
+}
+
+/// A report that contains every [ValidationError] of a validated value
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationReport {
+    src: String,
+    errors: Vec<ValidationError>,
+}
+
+impl ValidationReport {
+    /// generates an empty report for the type with the name `src`
+    pub fn new(src: String) -> Self {
+        ValidationReport {
+            src,
+            errors: vec![],
+        }
+    }
+
+    /// adds an error to the report
+    pub fn push(&mut self, err: ValidationError) {
+        self.errors.push(err);
+    }
+
+    /// adds the errors of the report of a field with nested validation, see
+    /// [ValidationError::nested_in]
+    pub fn push_nested(&mut self, field: &str, report: ValidationReport) {
+        for err in report.errors {
+            self.errors.push(err.nested_in(field, &self.src));
+        }
+    }
+
+    /// the name of the validated type
+    pub fn src(&self) -> &str {
+        &self.src
+    }
+
+    /// all errors in the order the rules are declared
+    pub fn errors(&self) -> &[ValidationError] {
+        &self.errors
+    }
+
+    /// true if there are no errors with [Severity::Error]
+    pub fn is_valid(&self) -> bool {
+        self.errors.iter().all(|e| e.severity() != Severity::Error)
+    }
+
+    /// transforms the report into a result with the first error of [Severity::Error]
+    pub fn into_result(self) -> Result<(), ValidationError> {
+        match self
+            .errors
+            .into_iter()
+            .find(|e| e.severity() == Severity::Error)
+        {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+impl Display for ValidationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.errors.is_empty() {
+            return write!(f, "'{}' is valid", self.src);
+        }
+        for (i, err) in self.errors.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}: {}", err.severity(), err)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serialize {
+    use serde::ser::SerializeStruct;
+    use serde::{Serialize, Serializer};
+
+    use super::*;
+    use crate::CmpOp;
+
+    impl Serialize for Severity {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(self)
+        }
+    }
+
+    impl Serialize for CmpOp {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(self.symbol())
+        }
+    }
+
+    /// The schema is stable: every key is always present, unknown details are `null`.
+    impl Serialize for ValidationError {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let v = self.violation();
+            let mut s = serializer.serialize_struct("ValidationError", 10)?;
+            s.serialize_field("type", self.src())?;
+            s.serialize_field("field", &v.map(|v| &v.field))?;
+            s.serialize_field("rule", &v.map(|v| &v.rule))?;
+            s.serialize_field("op", &v.map(|v| v.op))?;
+            s.serialize_field("bound", &v.map(|v| &v.bound))?;
+            s.serialize_field("value", &v.map(|v| &v.value))?;
+            s.serialize_field("bound_num", &v.and_then(|v| v.bound_num))?;
+            s.serialize_field("value_num", &v.and_then(|v| v.value_num))?;
+            s.serialize_field("severity", &self.severity())?;
+            s.serialize_field("message", self.msg())?;
+            s.end()
+        }
+    }
+
+    impl Serialize for ValidationReport {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut s = serializer.serialize_struct("ValidationReport", 3)?;
+            s.serialize_field("type", self.src())?;
+            s.serialize_field("valid", &self.is_valid())?;
+            s.serialize_field("errors", self.errors())?;
+            s.end()
+        }
+    }
+}
+
+/// Writes the reports as [JSON Lines](https://jsonlines.org/), one line per report.
+///
+/// Each line contains the index of the report as `record` in addition to the serialized
+/// [ValidationReport].
+#[cfg(feature = "serde")]
+pub fn write_json_lines<'a, W, I>(mut writer: W, reports: I) -> std::io::Result<()>
+where
+    W: std::io::Write,
+    I: IntoIterator<Item = &'a ValidationReport>,
+{
+    #[derive(serde::Serialize)]
+    struct Line<'a> {
+        record: usize,
+        #[serde(flatten)]
+        report: &'a ValidationReport,
+    }
+
+    for (record, report) in reports.into_iter().enumerate() {
+        serde_json::to_writer(&mut writer, &Line { record, report })?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_fixtures::DistillationColumn;
+    use crate::*;
+
+    #[test]
+    fn report_contains_all_violations() {
+        let dc = DistillationColumn::new(2, 5);
+        let report = dc.validate_all();
+        assert!(!report.is_valid());
+        assert_eq!(report.src(), "DistillationColumn");
+
+        let rules: Vec<_> = report.errors().iter().filter_map(|e| e.rule()).collect();
+        assert_eq!(rules, ["trays >= 3", "feed_place < trays"]);
+
+        let v = report.errors()[1].violation().unwrap();
+        assert_eq!(v.field, "feed_place");
+        assert_eq!(v.op, CmpOp::Lt);
+        assert_eq!(v.bound, "2");
+        assert_eq!(v.value, "5");
+        assert_eq!((v.bound_num, v.value_num), (Some(2.0), Some(5.0)));
+        assert_eq!(report.into_result(), dc.validate());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_lines_have_a_stable_schema() {
+        let dcs = [
+            DistillationColumn::new(10, 5),
+            DistillationColumn::new(10, 0),
+        ];
+        let reports: Vec<_> = dcs.iter().map(|dc| dc.validate_all()).collect();
+        let mut out = vec![];
+        write_json_lines(&mut out, &reports).unwrap();
+
+        let lines: Vec<serde_json::Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["valid"], true);
+        assert_eq!(
+            lines[1],
+            serde_json::json!({
+                "record": 1,
+                "type": "DistillationColumn",
+                "valid": false,
+                "errors": [{
+                    "type": "DistillationColumn",
+                    "field": "feed_place",
+                    "rule": "feed_place >= 1",
+                    "op": ">=",
+                    "bound": "1",
+                    "value": "0",
+                    "bound_num": 1.0,
+                    "value_num": 0.0,
+                    "severity": "error",
+                    "message": "value=0: 'feed_place' >= '1'",
+                }],
+            })
+        );
+    }
+}
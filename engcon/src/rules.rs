@@ -0,0 +1,823 @@
+//! Contracts that are loaded at runtime, e.g. plant-specific design limits that change without
+//! recompiling.
+//!
+//! A [RuleSet] uses the grammar of `#[validate_value(..)]`: `x <op> rhs` where `x` is the value
+//! of the field, `op` is one of `<`, `<=`, `>` and `>=` and `rhs` is a number or the name of
+//! another field. A rule file maps field names to a rule or a list of rules:
+//!
+//! ```toml
+//! name = "DistillationColumn"
+//!
+//! [rules]
+//! trays = ["x >= 3", "x <= 40"]
+//! feed_place = "x < trays, x >= 1"
+//!
+//! [warnings]
+//! trays = "x <= 30"
+//! ```
+//!
+//! The rules in `[warnings]` give errors with [Severity::Warning], they are reported but do not
+//! make a value invalid, see [ValidationReport::is_valid].
+//!
+//! The rules validate any [Serialize] value or a [Record] and give the same [ValidationError]s
+//! as the derive macro, i.e. with the same messages and rule ids.
+//!
+//! ```
+//! use engcon::rules::RuleSet;
+//! use serde_json::json;
+//!
+//! let rules = RuleSet::from_toml(r#"
+//!     [rules]
+//!     trays = "x <= 40"
+//!     feed_place = "x < trays"
+//! "#).unwrap();
+//!
+//! let err = rules.validate(&json!({"trays": 50, "feed_place": 5})).unwrap_err();
+//! assert_eq!(err.rule(), Some("trays <= 40"));
+//! assert_eq!(err.msg(), "value=50: 'trays' <= '40'");
+//! ```
+
+use std::{
+    borrow::Borrow,
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt::Display,
+    hash::Hash,
+    marker::PhantomData,
+    ops::Deref,
+    path::Path,
+};
+
+use serde::{de::MapAccess, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{CmpOp, Contracts, RuleViolation, Severity, ValidationError, ValidationReport};
+
+/// A single rule of a [RuleSet], the runtime counterpart of a [RuleContract](crate::RuleContract)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    /// a unique and readable identifier of the rule, e.g. `feed_place < trays`
+    pub id: String,
+    /// the path of the field the rule belongs to, e.g. `feed.flow` for nested values
+    pub field: String,
+    /// the comparison operator
+    pub op: CmpOp,
+    /// the right hand side as written in the rule
+    pub rhs: String,
+    /// the bound if the right hand side is a number
+    pub bound: Option<f64>,
+    /// the path of the other field if the right hand side refers to a field
+    pub sibling: Option<String>,
+    /// the type of the field if the rule set is typed by [RuleSet::for_type], values and bounds
+    /// are rounded to it like in the derived [Validator](crate::Validator)
+    pub ty: Option<&'static str>,
+    /// the severity of a violation, [Severity::Warning] for the rules in `[warnings]`
+    pub severity: Severity,
+}
+
+/// An error that occurs if a rule or a rule file cannot be parsed
+#[derive(Debug, Clone, PartialEq)]
+pub enum RuleError {
+    /// the rule does not follow the grammar `x <op> rhs`
+    Syntax {
+        field: String,
+        rule: String,
+        msg: String,
+    },
+    /// the rule file cannot be read or has an unexpected structure
+    File(String),
+    /// a rule refers to a field that the type does not have, e.g. a misspelled name
+    UnknownField { ty: &'static str, field: String },
+}
+
+/// Rules that are defined at runtime, see the [module documentation](self)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleSet {
+    name: Option<String>,
+    rules: Vec<Rule>,
+}
+
+/// A [RuleSet] whose fields are checked against the fields of `T`, see [RuleSet::for_type].
+///
+/// Only a typed rule set is an overlay for
+/// [Validated::try_new_with_overlay](crate::Validated::try_new_with_overlay).
+pub struct TypedRuleSet<T> {
+    rules: RuleSet,
+    ty: PhantomData<fn() -> T>,
+}
+
+/// A record of named numeric values that can be validated by a [RuleSet]
+pub trait Record {
+    /// the value at the given path, `None` if it is missing or not a number
+    fn number(&self, path: &str) -> Option<f64>;
+}
+
+impl Rule {
+    /// Parses a rule like `x >= 3` or `x < trays` on the given field.
+    ///
+    /// A number is kept as written, [RuleSet::for_type] writes it like the derive macro does for
+    /// the type of the field, e.g. `x > 0` on a `f64` becomes `x > 0.0`.
+    pub fn parse(field: &str, rule: &str) -> Result<Self, RuleError> {
+        let syntax = |msg: &str| RuleError::Syntax {
+            field: field.to_owned(),
+            rule: rule.to_owned(),
+            msg: msg.to_owned(),
+        };
+
+        let rest = rule
+            .trim()
+            .strip_prefix('x')
+            .ok_or_else(|| syntax("a rule has to start with 'x', e.g. 'x >= 3'"))?
+            .trim_start();
+        let (op, rhs) = [CmpOp::Le, CmpOp::Ge, CmpOp::Lt, CmpOp::Gt]
+            .into_iter()
+            .find_map(|op| rest.strip_prefix(op.symbol()).map(|rhs| (op, rhs.trim())))
+            .ok_or_else(|| syntax("expected one of the operators '<', '<=', '>' or '>='"))?;
+        if rhs.is_empty() {
+            return Err(syntax("the right hand side is missing"));
+        }
+
+        let mut parsed = Rule {
+            id: format!("{} {} {}", field, op, rhs),
+            field: field.to_owned(),
+            op,
+            rhs: rhs.to_owned(),
+            bound: None,
+            sibling: None,
+            ty: None,
+            severity: Severity::Error,
+        };
+        if let Ok(bound) = rhs.replace('_', "").parse::<f64>() {
+            // `inf`, `NaN` or an overflow like `1e400` are no valid bounds
+            if !bound.is_finite() {
+                return Err(syntax("the bound has to be a finite number"));
+            }
+            parsed.bound = Some(bound);
+        } else if rhs == "ctx" || rhs.starts_with("ctx.") {
+            return Err(syntax(
+                "runtime rules have no context, use a number or the name of a field",
+            ));
+        } else if is_path(rhs) {
+            parsed.sibling = Some(rhs.to_owned());
+        } else {
+            return Err(syntax(
+                "the right hand side has to be a number or the name of a field",
+            ));
+        }
+        Ok(parsed)
+    }
+
+    /// Writes the bound like the derive macro does for a field of the type `ty`, integers on
+    /// float fields get a fractional part and fractions on integer fields are an error. The
+    /// bound is rounded to the type, e.g. `0.3` on a `f32` is `0.30000001192092896`.
+    fn coerce_to(&mut self, ty: &'static str) -> Result<(), RuleError> {
+        self.ty = Some(ty);
+        let Some(bound) = self.bound else {
+            return Ok(());
+        };
+        self.bound = Some(self.round(bound));
+        let digits = self.rhs.strip_prefix('-').unwrap_or(&self.rhs);
+        let integer = digits.chars().all(|c| c.is_ascii_digit() || c == '_');
+        let float_ty = matches!(ty, "f32" | "f64");
+        let integer_ty = !float_ty && INTEGER_TYPES.contains(&ty);
+
+        if float_ty && integer {
+            self.rhs = format!("{}.0", self.rhs.replace('_', ""));
+            self.id = format!("{} {} {}", self.field, self.op, self.rhs);
+        } else if integer_ty && !integer {
+            return Err(RuleError::Syntax {
+                field: self.field.clone(),
+                rule: format!("x {} {}", self.op, self.rhs),
+                msg: format!(
+                    "expected an integer for a field of type '{}', found '{}'",
+                    ty, self.rhs
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// checks the rule on the record, `None` if it holds
+    fn check<R: Record + ?Sized>(&self, record: &R, src: &str) -> Option<ValidationError> {
+        let missing = |path: &str| {
+            ValidationError::new(
+                format!(
+                    "'{}' is missing or not a number, it is needed by '{}'",
+                    path, self.id
+                ),
+                src.to_owned(),
+            )
+            .with_severity(self.severity)
+        };
+
+        let Some(value) = record.number(&self.field) else {
+            return Some(missing(&self.field));
+        };
+        let value = self.round(value);
+        let bound = match (&self.sibling, self.bound) {
+            (Some(sibling), _) => match record.number(sibling) {
+                Some(bound) => self.round(bound),
+                None => return Some(missing(sibling)),
+            },
+            (None, Some(bound)) => bound,
+            (None, None) => unreachable!("a parsed rule has a bound or a sibling"),
+        };
+        if self.op.eval(value, bound) {
+            return None;
+        }
+
+        Some(
+            ValidationError::new(
+                format!(
+                    "value={}: '{}' {} '{}'",
+                    self.format(value),
+                    self.field,
+                    self.op,
+                    self.rhs
+                ),
+                src.to_owned(),
+            )
+            .with_violation(RuleViolation {
+                field: self.field.clone(),
+                rule: self.id.clone(),
+                op: self.op,
+                bound: self.format(bound),
+                value: self.format(value),
+                bound_num: Some(bound),
+                value_num: Some(value),
+            })
+            .with_severity(self.severity),
+        )
+    }
+
+    /// rounds a number to the type of the field, only `f32` is less precise than `f64`
+    fn round(&self, number: f64) -> f64 {
+        match self.ty {
+            Some("f32") => number as f32 as f64,
+            _ => number,
+        }
+    }
+
+    /// formats a number like the derive macro does for the type of the field, e.g. `0.3` and not
+    /// `0.30000001192092896` for a `f32`
+    fn format(&self, number: f64) -> String {
+        match self.ty {
+            Some("f32") => (number as f32).to_string(),
+            _ => number.to_string(),
+        }
+    }
+}
+
+const INTEGER_TYPES: &[&str] = &[
+    "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize",
+];
+
+/// true for field paths like `trays` or `feed.flow`
+fn is_path(s: &str) -> bool {
+    !s.starts_with(|c: char| c.is_ascii_digit())
+        && s.split('.').all(|segment| {
+            !segment.is_empty()
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+}
+
+impl RuleSet {
+    /// generates an empty rule set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// sets the name that is used as the source of the errors, e.g. the name of a type
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Adds the rules on the given field, multiple rules are separated by a comma as in
+    /// `#[validate_value(x >= 1, x < trays)]`
+    pub fn add(&mut self, field: &str, rules: &str) -> Result<&mut Self, RuleError> {
+        for rule in rules.split(',') {
+            self.rules.push(Rule::parse(field, rule)?);
+        }
+        Ok(self)
+    }
+
+    /// adds rules like [RuleSet::add] whose violations are reported with [Severity::Warning]
+    pub fn add_warning(&mut self, field: &str, rules: &str) -> Result<&mut Self, RuleError> {
+        for rule in rules.split(',') {
+            let mut rule = Rule::parse(field, rule)?;
+            rule.severity = Severity::Warning;
+            self.rules.push(rule);
+        }
+        Ok(self)
+    }
+
+    /// parses a rule file in the TOML format, see the [module documentation](self)
+    pub fn from_toml(src: &str) -> Result<Self, RuleError> {
+        toml::from_str::<RuleFile>(src)
+            .map_err(|err| RuleError::File(err.to_string()))?
+            .into_rule_set()
+    }
+
+    /// parses a rule file in the JSON format, e.g. `{"rules": {"trays": "x <= 40"}}`
+    pub fn from_json(src: &str) -> Result<Self, RuleError> {
+        serde_json::from_str::<RuleFile>(src)
+            .map_err(|err| RuleError::File(err.to_string()))?
+            .into_rule_set()
+    }
+
+    /// parses a rule file in the YAML format
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(src: &str) -> Result<Self, RuleError> {
+        serde_norway::from_str::<RuleFile>(src)
+            .map_err(|err| RuleError::File(err.to_string()))?
+            .into_rule_set()
+    }
+
+    /// Loads a rule file, the format is given by the extension `toml`, `json` or, with the
+    /// `yaml` feature, `yaml` and `yml`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RuleError> {
+        let path = path.as_ref();
+        let src = std::fs::read_to_string(path)
+            .map_err(|err| RuleError::File(format!("{}: {}", path.display(), err)))?;
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Self::from_toml(&src),
+            Some("json") => Self::from_json(&src),
+            #[cfg(feature = "yaml")]
+            Some("yaml" | "yml") => Self::from_yaml(&src),
+            _ => Err(RuleError::File(format!(
+                "{}: unsupported format of the rule file",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Checks that every rule refers to fields of `T` and names the rule set after `T`, such
+    /// that the errors look like the errors of the derived [Validator](crate::Validator).
+    ///
+    /// Paths into nested values like `feed.flow` are checked by their first segment. Numbers
+    /// are written like the derive macro does for the type of the field, such that the rule ids
+    /// are equal, e.g. `ratio > 0.0` for `x > 0` on a `f64`.
+    pub fn for_type<T: Contracts>(mut self) -> Result<TypedRuleSet<T>, RuleError> {
+        for rule in &mut self.rules {
+            for path in std::iter::once(&rule.field).chain(&rule.sibling) {
+                let field = path.split('.').next().unwrap_or(path);
+                if !T::FIELDS.contains(&field) {
+                    return Err(RuleError::UnknownField {
+                        ty: T::TYPE_NAME,
+                        field: path.clone(),
+                    });
+                }
+            }
+            if let Some(contract) = T::field_contract(&rule.field) {
+                rule.coerce_to(contract.ty)?;
+            }
+        }
+        self.name = Some(T::TYPE_NAME.to_owned());
+        Ok(TypedRuleSet {
+            rules: self,
+            ty: PhantomData,
+        })
+    }
+
+    /// the name that is used as the source of the errors
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// the rules in the order of the rule file
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// the number of rules
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// true if there are no rules
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Validates a record and returns the first [ValidationError] with [Severity::Error], the
+    /// source of the error is the name of the rule set or `record`
+    pub fn validate_record<R: Record + ?Sized>(&self, record: &R) -> Result<(), ValidationError> {
+        let src = self.name.as_deref().unwrap_or("record");
+        match self
+            .rules
+            .iter()
+            .filter(|rule| rule.severity == Severity::Error)
+            .find_map(|rule| rule.check(record, src))
+        {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    /// checks every rule on a record and collects all violations in a [ValidationReport]
+    pub fn validate_record_all<R: Record + ?Sized>(&self, record: &R) -> ValidationReport {
+        let src = self.name.as_deref().unwrap_or("record");
+        self.check_all(record, src)
+    }
+
+    /// Validates a value by its serialized fields, the source of the error is the name of the
+    /// rule set or the name of the type `T`
+    pub fn validate<T: Serialize + ?Sized>(&self, value: &T) -> Result<(), ValidationError> {
+        self.validate_all(value).into_result()
+    }
+
+    /// checks every rule on the serialized fields of a value and collects all violations
+    pub fn validate_all<T: Serialize + ?Sized>(&self, value: &T) -> ValidationReport {
+        let src = match &self.name {
+            Some(name) => name.clone(),
+            None => short_type_name::<T>(),
+        };
+        match serde_json::to_value(value) {
+            Ok(record) => self.check_all(&record, &src),
+            Err(err) => {
+                let mut report = ValidationReport::new(src.clone());
+                report.push(ValidationError::new(
+                    format!("cannot serialize the value: {}", err),
+                    src,
+                ));
+                report
+            }
+        }
+    }
+
+    fn check_all<R: Record + ?Sized>(&self, record: &R, src: &str) -> ValidationReport {
+        let mut report = ValidationReport::new(src.to_owned());
+        for err in self.rules.iter().filter_map(|rule| rule.check(record, src)) {
+            report.push(err);
+        }
+        report
+    }
+}
+
+/// the name of the type without its module path, e.g. `DistillationColumn`
+fn short_type_name<T: ?Sized>() -> String {
+    let name = std::any::type_name::<T>();
+    let name = name.split('<').next().unwrap_or(name);
+    name.rsplit("::").next().unwrap_or(name).to_owned()
+}
+
+impl<T> TypedRuleSet<T> {
+    /// the rules without the type, e.g. to store rule sets of several types together
+    pub fn into_inner(self) -> RuleSet {
+        self.rules
+    }
+}
+
+impl<T> Deref for TypedRuleSet<T> {
+    type Target = RuleSet;
+
+    fn deref(&self) -> &Self::Target {
+        &self.rules
+    }
+}
+
+impl<T> Clone for TypedRuleSet<T> {
+    fn clone(&self) -> Self {
+        TypedRuleSet {
+            rules: self.rules.clone(),
+            ty: PhantomData,
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for TypedRuleSet<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TypedRuleSet")
+            .field("ty", &std::any::type_name::<T>())
+            .field("rules", &self.rules)
+            .finish()
+    }
+}
+
+impl Record for Value {
+    /// objects and arrays are indexed by the segments of the path, e.g. `feeds.0.flow`
+    fn number(&self, path: &str) -> Option<f64> {
+        path.split('.')
+            .try_fold(self, |value, key| match value {
+                Value::Object(map) => map.get(key),
+                Value::Array(values) => key.parse::<usize>().ok().and_then(|i| values.get(i)),
+                _ => None,
+            })?
+            .as_f64()
+    }
+}
+
+impl<K: Borrow<str> + Hash + Eq> Record for HashMap<K, f64> {
+    fn number(&self, path: &str) -> Option<f64> {
+        self.get(path).copied()
+    }
+}
+
+impl<K: Borrow<str> + Ord> Record for BTreeMap<K, f64> {
+    fn number(&self, path: &str) -> Option<f64> {
+        self.get(path).copied()
+    }
+}
+
+/// the structure of a rule file
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleFile {
+    name: Option<String>,
+    #[serde(default)]
+    rules: FieldRules,
+    #[serde(default)]
+    warnings: FieldRules,
+}
+
+/// the rules by field in the order of the file
+#[derive(Default)]
+struct FieldRules(Vec<(String, OneOrMany)>);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl<'de> Deserialize<'de> for FieldRules {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = FieldRules;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "a map of field names to a rule or a list of rules")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut rules = vec![];
+                while let Some(entry) = map.next_entry()? {
+                    rules.push(entry);
+                }
+                Ok(FieldRules(rules))
+            }
+        }
+
+        deserializer.deserialize_map(Visitor)
+    }
+}
+
+impl OneOrMany {
+    fn into_vec(self) -> Vec<String> {
+        match self {
+            OneOrMany::One(rule) => vec![rule],
+            OneOrMany::Many(rules) => rules,
+        }
+    }
+}
+
+impl RuleFile {
+    fn into_rule_set(self) -> Result<RuleSet, RuleError> {
+        let mut set = RuleSet {
+            name: self.name,
+            rules: vec![],
+        };
+        for (field, rules) in self.rules.0 {
+            for rule in rules.into_vec() {
+                set.add(&field, &rule)?;
+            }
+        }
+        for (field, rules) in self.warnings.0 {
+            for rule in rules.into_vec() {
+                set.add_warning(&field, &rule)?;
+            }
+        }
+        Ok(set)
+    }
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.id)
+    }
+}
+
+impl Display for RuleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleError::Syntax { field, rule, msg } => {
+                write!(f, "invalid rule '{}' on '{}': {}", rule, field, msg)
+            }
+            RuleError::File(msg) => write!(f, "invalid rule file: {}", msg),
+            RuleError::UnknownField { ty, field } => {
+                write!(f, "'{}' has no field '{}'", ty, field)
+            }
+        }
+    }
+}
+
+impl Error for RuleError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::DistillationColumn;
+    use crate::*;
+
+    const RULES: &str = r#"
+        name = "DistillationColumn"
+
+        [rules]
+        trays = "x >= 3"
+        feed_place = ["x < trays", "x >= 1"]
+        distiliate_to_feed_ratio = "x > 0.0, x < 1.0"
+    "#;
+
+    #[test]
+    fn rules_are_parsed() {
+        let rule = Rule::parse("feed_place", " x<trays ").unwrap();
+        assert_eq!(rule.id, "feed_place < trays");
+        assert_eq!(rule.sibling.as_deref(), Some("trays"));
+
+        let rule = Rule::parse("flow", "x <= 1_000.5").unwrap();
+        assert_eq!((rule.op, rule.bound), (CmpOp::Le, Some(1000.5)));
+
+        let invalid = [
+            "y > 3",
+            "x == 3",
+            "x >",
+            "x < ctx.max",
+            "x < 3 bar",
+            "x < inf",
+            "x > NaN",
+            "x < 1e400",
+        ];
+        for invalid in invalid {
+            assert!(
+                matches!(Rule::parse("trays", invalid), Err(RuleError::Syntax { .. })),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn rule_files_keep_the_order_of_the_fields() {
+        let rules = RuleSet::from_toml(RULES).unwrap();
+        assert_eq!(rules.name(), Some("DistillationColumn"));
+        let ids: Vec<_> = rules.rules().iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "trays >= 3",
+                "feed_place < trays",
+                "feed_place >= 1",
+                "distiliate_to_feed_ratio > 0.0",
+                "distiliate_to_feed_ratio < 1.0"
+            ]
+        );
+
+        let json = RuleSet::from_json(r#"{"rules": {"trays": "x >= 3"}}"#).unwrap();
+        assert_eq!(json.len(), 1);
+        assert!(matches!(
+            RuleSet::from_toml("[rule]\ntrays = \"x >= 3\""),
+            Err(RuleError::File(_))
+        ));
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn rule_files_may_be_yaml() {
+        let rules = RuleSet::from_yaml("rules:\n  trays: x >= 3\n  feed_place: [x < trays]\n");
+        assert_eq!(rules.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn errors_equal_the_derived_errors() {
+        let rules = RuleSet::from_toml(RULES).unwrap();
+        let cases = [(10, 5, 0.5), (2, 5, 0.5), (10, 12, 0.5), (10, 0, 1.0)];
+        for (trays, feed_place, distiliate_to_feed_ratio) in cases {
+            let dc = DistillationColumn {
+                trays,
+                feed_place,
+                distiliate_to_feed_ratio,
+            };
+            assert_eq!(rules.validate(&dc), dc.validate());
+            assert_eq!(rules.validate_all(&dc).errors(), dc.validate_all().errors());
+        }
+    }
+
+    #[derive(Debug, serde::Serialize, engcon_macros::Validatable)]
+    struct Reflux {
+        #[validate_value(x > 0.1, x <= 0.3)]
+        ratio: f32,
+    }
+
+    #[test]
+    fn f32_bounds_are_rounded_like_the_derived_rules() {
+        let rules = RuleSet::from_toml("[rules]\nratio = \"x > 0.1, x <= 0.3\"").unwrap();
+        let rules = rules.for_type::<Reflux>().unwrap();
+        // neither 0.1 nor 0.3 is exactly representable as `f32`
+        for ratio in [0.3, 0.1, 0.31, 0.2] {
+            let reflux = Reflux { ratio };
+            assert_eq!(rules.validate(&reflux), reflux.validate(), "{}", ratio);
+        }
+
+        let err = rules.validate(&Reflux { ratio: 0.31 }).unwrap_err();
+        assert_eq!(err.msg(), "value=0.31: 'ratio' <= '0.3'");
+    }
+
+    #[test]
+    fn overlays_are_checked_against_the_fields_of_the_type() {
+        let overlay = RuleSet::from_toml("[rules]\ntrays = \"x <= 40\"").unwrap();
+        let overlay = overlay.for_type::<DistillationColumn>().unwrap();
+        assert_eq!(overlay.name(), Some("DistillationColumn"));
+
+        let typo = RuleSet::from_toml("[rules]\ntray = \"x <= 40\"").unwrap();
+        assert_eq!(
+            typo.for_type::<DistillationColumn>().unwrap_err(),
+            RuleError::UnknownField {
+                ty: "DistillationColumn",
+                field: "tray".to_owned()
+            }
+        );
+
+        let coerced = RuleSet::from_toml("[rules]\ndistiliate_to_feed_ratio = \"x > 0, x < 1\"");
+        let coerced = coerced.unwrap().for_type::<DistillationColumn>().unwrap();
+        let ids: Vec<_> = coerced.rules().iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "distiliate_to_feed_ratio > 0.0",
+                "distiliate_to_feed_ratio < 1.0"
+            ]
+        );
+        let fraction = RuleSet::from_toml("[rules]\ntrays = \"x <= 40.5\"").unwrap();
+        assert!(matches!(
+            fraction.for_type::<DistillationColumn>(),
+            Err(RuleError::Syntax { .. })
+        ));
+
+        let dc = DistillationColumn {
+            trays: 50,
+            feed_place: 5,
+            distiliate_to_feed_ratio: 0.5,
+        };
+        let err = Validated::try_new_with_overlay(dc.clone(), &overlay).unwrap_err();
+        assert_eq!(err.rule(), Some("trays <= 40"));
+        assert_eq!(err.src(), "DistillationColumn");
+
+        // the compiled contracts are checked first
+        let dc = DistillationColumn {
+            feed_place: 60,
+            ..dc
+        };
+        let err = Validated::try_new_with_overlay(dc, &overlay).unwrap_err();
+        assert_eq!(err.rule(), Some("feed_place < trays"));
+    }
+
+    #[test]
+    fn warnings_do_not_invalidate() {
+        let rules =
+            RuleSet::from_toml("[rules]\ntrays = \"x <= 40\"\n\n[warnings]\ntrays = \"x <= 30\"")
+                .unwrap();
+        assert_eq!(rules.rules()[1].severity, Severity::Warning);
+
+        let record = serde_json::json!({"trays": 35});
+        let report = rules.validate_record_all(&record);
+        assert_eq!(report.errors().len(), 1);
+        assert_eq!(report.errors()[0].severity(), Severity::Warning);
+        assert_eq!(report.errors()[0].rule(), Some("trays <= 30"));
+        assert!(report.is_valid());
+        assert!(rules.validate(&record).is_ok());
+        assert!(rules.validate_record(&record).is_ok());
+
+        let report = rules.validate_all(&serde_json::json!({"trays": 50}));
+        assert!(!report.is_valid());
+        assert_eq!(
+            report.into_result().unwrap_err().rule(),
+            Some("trays <= 40")
+        );
+    }
+
+    #[test]
+    fn records_are_validated() {
+        let mut rules = RuleSet::new();
+        rules.add("feed.flow", "x > 0.0").unwrap();
+        rules.add("trays", "x <= 40").unwrap();
+
+        let record = serde_json::json!({"feed": {"flow": -1.0}, "trays": 12});
+        let err = rules.validate_record(&record).unwrap_err();
+        assert_eq!(err.src(), "record");
+        assert_eq!(err.field(), Some("feed.flow"));
+
+        let record: HashMap<&str, f64> = [("trays", 50.0)].into();
+        let report = rules.validate_record_all(&record);
+        assert_eq!(report.errors().len(), 2);
+        assert!(report.errors()[0]
+            .msg()
+            .starts_with("'feed.flow' is missing"));
+        assert_eq!(report.errors()[1].rule(), Some("trays <= 40"));
+    }
+}
@@ -0,0 +1,348 @@
+//! Sampling of valid instances for design-space exploration, e.g. Monte-Carlo studies.
+//!
+//! The derive macro implements [Sample] for types whose validated fields are primitive
+//! numbers, independent of the enabled features. It draws the fields in dependency order,
+//! e.g. `trays` first and then `feed_place` in `[1, trays)` for `x < trays, x >= 1`. With the
+//! `rand` feature [UniformSampler] draws uniformly distributed values with a seedable random
+//! number generator.
+//!
+//! Values are drawn as `f64`, i.e. integers beyond 2^53 like a large `u64` are not drawn
+//! exactly.
+
+/// The valid range of a field given the values of the fields drawn before
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SampleRange {
+    /// the name of the field
+    pub field: &'static str,
+    /// true for integer fields, the bounds of integer fields are never strict
+    pub integer: bool,
+    /// the lower bound, `-inf` if unbounded
+    pub lower: f64,
+    /// true if the lower bound itself is not valid
+    pub lower_strict: bool,
+    /// the upper bound, `inf` if unbounded
+    pub upper: f64,
+    /// true if the upper bound itself is not valid
+    pub upper_strict: bool,
+}
+
+/// Draws the validated fields from the range given by their rules.
+///
+/// The type parameter `C` is the context of types that implement
+/// [ValidatorWithContext](crate::ValidatorWithContext), `()` otherwise.
+pub trait Sample<C = ()> {
+    /// Sets every validated field to a value given by `draw` in dependency order.
+    ///
+    /// A field whose value violates a rule, e.g. after rounding to the field type, is drawn
+    /// again up to [MAX_TRIES](SampleRange::MAX_TRIES) times. Gives false if a field keeps an
+    /// invalid value, e.g. for contradicting rules. Other fields are not changed.
+    fn sample_fields(&mut self, ctx: &C, draw: &mut dyn FnMut(&SampleRange) -> f64) -> bool;
+}
+
+impl SampleRange {
+    /// the number of times a field is drawn again if rounding to the field type hits a
+    /// strict bound
+    pub const MAX_TRIES: usize = 16;
+
+    /// the number of values an unbounded side is limited to by default
+    pub const DEFAULT_SPAN: f64 = 100.0;
+
+    /// generates an unbounded range
+    pub fn new(field: &'static str, integer: bool) -> Self {
+        SampleRange {
+            field,
+            integer,
+            lower: f64::NEG_INFINITY,
+            lower_strict: false,
+            upper: f64::INFINITY,
+            upper_strict: false,
+        }
+    }
+
+    /// applies a lower bound if it is tighter than the current one
+    pub fn restrict_lower(&mut self, bound: f64, strict: bool) {
+        let (bound, strict) = match (self.integer, strict) {
+            (true, true) => (bound.floor() + 1.0, false),
+            (true, false) => (bound.ceil(), false),
+            (false, _) => (bound, strict),
+        };
+        if bound > self.lower || (bound == self.lower && strict) {
+            self.lower = bound;
+            self.lower_strict = strict;
+        }
+    }
+
+    /// applies an upper bound if it is tighter than the current one
+    pub fn restrict_upper(&mut self, bound: f64, strict: bool) {
+        let (bound, strict) = match (self.integer, strict) {
+            (true, true) => (bound.ceil() - 1.0, false),
+            (true, false) => (bound.floor(), false),
+            (false, _) => (bound, strict),
+        };
+        if bound < self.upper || (bound == self.upper && strict) {
+            self.upper = bound;
+            self.upper_strict = strict;
+        }
+    }
+
+    /// true if the value lies in the range
+    pub fn contains(&self, value: f64) -> bool {
+        let above = if self.lower_strict {
+            value > self.lower
+        } else {
+            value >= self.lower
+        };
+        let below = if self.upper_strict {
+            value < self.upper
+        } else {
+            value <= self.upper
+        };
+        above && below
+    }
+
+    /// true if no value lies in the range
+    pub fn is_empty(&self) -> bool {
+        self.lower > self.upper
+            || (self.lower == self.upper && (self.lower_strict || self.upper_strict))
+    }
+
+    /// the bounds with unbounded sides limited to `span` values starting at the finite
+    /// bound, a range without bounds is centered around 0
+    pub fn limited(&self, span: f64) -> (f64, f64) {
+        match (self.lower.is_finite(), self.upper.is_finite()) {
+            (true, true) => (self.lower, self.upper),
+            (true, false) => (self.lower, self.lower + span),
+            (false, true) => (self.upper - span, self.upper),
+            (false, false) => (-span / 2.0, span / 2.0),
+        }
+    }
+
+    /// maps `unit` in `[0, 1]` linearly onto the range [limited](Self::limited) by `span`,
+    /// the lower bound is returned for empty ranges.
+    ///
+    /// Strict bounds keep a distance of `1e-6` times the width, which survives the rounding
+    /// to `f32`.
+    pub fn interpolate(&self, unit: f64, span: f64) -> f64 {
+        let (lower, upper) = self.limited(span);
+        if self.is_empty() || lower >= upper {
+            return lower;
+        }
+
+        let unit = unit.clamp(0.0, 1.0);
+        if self.integer {
+            return (lower + (unit * (upper - lower + 1.0)).floor()).min(upper);
+        }
+        let min = if self.lower_strict { 1e-6 } else { 0.0 };
+        let max = if self.upper_strict { 1.0 - 1e-6 } else { 1.0 };
+        lerp(lower, upper, unit.clamp(min, max))
+    }
+}
+
+/// the point at `unit` between the finite bounds, the width `upper - lower` may overflow for
+/// wide bounds like `-1e308` and `1e308`, the weighted sum does not
+fn lerp(lower: f64, upper: f64, unit: f64) -> f64 {
+    (lower * (1.0 - unit) + upper * unit).clamp(lower, upper)
+}
+
+#[cfg(feature = "rand")]
+pub use uniform::*;
+
+#[cfg(feature = "rand")]
+mod uniform {
+    use rand::{Rng, RngExt};
+
+    use super::*;
+    use crate::{Validated, ValidationError, Validator};
+
+    /// Draws uniformly distributed values in the ranges of the fields.
+    ///
+    /// A range that is unbounded on one side is limited to `span` values starting at the
+    /// finite bound, a range without bounds is centered around 0.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct UniformSampler {
+        pub span: f64,
+    }
+
+    impl Default for UniformSampler {
+        fn default() -> Self {
+            UniformSampler {
+                span: SampleRange::DEFAULT_SPAN,
+            }
+        }
+    }
+
+    impl UniformSampler {
+        /// generates a sampler that limits unbounded ranges to `span` values
+        pub fn new(span: f64) -> Self {
+            UniformSampler { span }
+        }
+
+        /// Draws a value in the range, the lower bound is returned for empty ranges.
+        ///
+        /// A value that hits a strict bound is drawn again up to
+        /// [MAX_TRIES](SampleRange::MAX_TRIES) times. Strict bounds without a float between
+        /// them give the lower bound as well, i.e. an invalid value that fails the validation.
+        pub fn draw<R: Rng + ?Sized>(&self, range: &SampleRange, rng: &mut R) -> f64 {
+            let (lower, upper) = range.limited(self.span);
+            if range.is_empty() || lower >= upper {
+                return lower;
+            }
+
+            if range.integer {
+                return rng.random_range(lower as i64..=upper as i64) as f64;
+            }
+            for _ in 0..SampleRange::MAX_TRIES {
+                let value = lerp(lower, upper, rng.random_range(0.0..=1.0));
+                if range.contains(value) {
+                    return value;
+                }
+            }
+            lower
+        }
+
+        /// draws the validated fields of `target` in place, false if a field keeps an invalid
+        /// value, see [Sample::sample_fields]
+        pub fn sample_into<T, C, R>(&self, target: &mut T, ctx: &C, rng: &mut R) -> bool
+        where
+            T: Sample<C>,
+            R: Rng + ?Sized,
+        {
+            target.sample_fields(ctx, &mut |range| self.draw(range, rng))
+        }
+
+        /// draws an instance, fields without rules keep their default value
+        pub fn sample<T, R>(&self, rng: &mut R) -> T
+        where
+            T: Sample + Default,
+            R: Rng + ?Sized,
+        {
+            self.sample_with(&(), rng)
+        }
+
+        /// draws an instance whose rules depend on the context `ctx`
+        pub fn sample_with<T, C, R>(&self, ctx: &C, rng: &mut R) -> T
+        where
+            T: Sample<C> + Default,
+            R: Rng + ?Sized,
+        {
+            let mut target = T::default();
+            self.sample_into(&mut target, ctx, rng);
+            target
+        }
+
+        /// draws an instance and validates it, fails if the rules of a field contradict
+        /// each other for the values drawn before
+        pub fn sample_validated<T, R>(&self, rng: &mut R) -> Result<Validated<T>, ValidationError>
+        where
+            T: Sample + Validator + Default,
+            R: Rng + ?Sized,
+        {
+            self.sample::<T, R>(rng).try_into_validated()
+        }
+    }
+
+    /// draws an instance with the default [UniformSampler]
+    pub fn sample<T, R>(rng: &mut R) -> T
+    where
+        T: Sample + Default,
+        R: Rng + ?Sized,
+    {
+        UniformSampler::default().sample(rng)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::DistillationColumn;
+
+    #[test]
+    fn fields_are_drawn_in_dependency_order() {
+        let mut drawn = vec![];
+        let mut dc = DistillationColumn::default();
+        dc.sample_fields(&(), &mut |range| {
+            drawn.push(*range);
+            range.limited(SampleRange::DEFAULT_SPAN).1
+        });
+
+        assert_eq!(drawn[0].field, "trays");
+        assert_eq!((drawn[0].lower, drawn[0].upper), (3.0, f64::INFINITY));
+        assert_eq!(drawn[1].field, "feed_place");
+        assert_eq!((drawn[1].lower, drawn[1].upper), (1.0, 102.0));
+        assert_eq!(dc.feed_place, 102);
+        assert!(drawn[2].lower_strict && drawn[2].upper_strict);
+    }
+
+    #[derive(Debug, Default, engcon_macros::Validatable)]
+    struct Narrow {
+        // no `f32` lies between the bounds
+        #[validate_value(x > 1.0, x < 1.0000001)]
+        ratio: f32,
+    }
+
+    #[test]
+    fn invalid_draws_are_reported() {
+        let mut dc = DistillationColumn::default();
+        let mut draws = 0;
+        // the strict upper bound of the ratio is invalid
+        let valid = dc.sample_fields(&(), &mut |range| {
+            draws += 1;
+            range.limited(SampleRange::DEFAULT_SPAN).1
+        });
+        assert!(!valid);
+        assert_eq!(draws, 2 + SampleRange::MAX_TRIES);
+
+        let mut narrow = Narrow::default();
+        assert!(!narrow.sample_fields(&(), &mut |range| range.interpolate(0.5, 1.0)));
+        assert!(dc.sample_fields(&(), &mut |range| range.interpolate(0.5, 1.0)));
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn samples_are_valid_and_reproducible() {
+        use crate::Validator;
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let sampler = UniformSampler::default();
+        let mut rng = StdRng::seed_from_u64(42);
+        let samples: Vec<DistillationColumn> = (0..200).map(|_| sampler.sample(&mut rng)).collect();
+        assert!(samples.iter().all(|dc| dc.validate().is_ok()));
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let again: DistillationColumn = sample(&mut rng);
+        assert_eq!(again, samples[0]);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn wide_bounds_are_sampled_without_overflow() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut range = SampleRange::new("pressure", false);
+        range.restrict_lower(-1e308, true);
+        range.restrict_upper(1e308, true);
+
+        let mut rng = StdRng::seed_from_u64(42);
+        for _ in 0..100 {
+            let value = UniformSampler::default().draw(&range, &mut rng);
+            assert!(value.is_finite() && range.contains(value));
+        }
+        assert!(range.contains(range.interpolate(0.5, SampleRange::DEFAULT_SPAN)));
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn strict_bounds_without_a_value_between_them_stop() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut range = SampleRange::new("ratio", false);
+        range.restrict_lower(1.0, true);
+        range.restrict_upper(1.0 + f64::EPSILON, true);
+        assert!(!range.is_empty());
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let value = UniformSampler::default().draw(&range, &mut rng);
+        assert_eq!(value, 1.0);
+        assert!(!range.contains(value));
+    }
+}
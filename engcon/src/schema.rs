@@ -0,0 +1,114 @@
+//! Export of the contracts as [JSON Schema](https://json-schema.org/).
+//!
+//! Constant bounds are mapped to `minimum`, `exclusiveMinimum`, `maximum` and `exclusiveMaximum`.
+//! Rules that JSON Schema cannot express, e.g. `x < trays`, are emitted in the vendor
+//! extension keyword [RELATIONS_KEYWORD].
+
+use serde_json::{json, Map, Value};
+
+use crate::{CmpOp, Contracts, FieldContract};
+
+/// The vendor extension keyword that holds the rules JSON Schema cannot express
+pub const RELATIONS_KEYWORD: &str = "x-engcon-relations";
+
+/// Generates a JSON Schema (draft 2020-12) of the validated fields of `T`
+pub fn json_schema<T: Contracts>() -> Value {
+    let properties: Map<String, Value> = T::CONTRACTS
+        .iter()
+        .map(|f| (f.field.to_owned(), field_schema(f)))
+        .collect();
+    let required: Vec<&str> = T::CONTRACTS.iter().map(|f| f.field).collect();
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": T::TYPE_NAME,
+        "type": "object",
+        "properties": properties,
+        "required": required,
+    })
+}
+
+fn field_schema(field: &FieldContract) -> Value {
+    let mut schema = Map::new();
+    if let Some(ty) = json_type(field) {
+        schema.insert("type".to_owned(), json!(ty));
+    }
+
+    let mut relations = vec![];
+    for rule in field.rules {
+        let Some(bound) = rule.bound else {
+            relations.push(json!({
+                "rule": rule.id,
+                "op": rule.op.symbol(),
+                "rhs": rule.rhs,
+            }));
+            continue;
+        };
+
+        // keep the tightest bound if a keyword is used multiple times
+        let (keyword, tighter): (&str, fn(f64, f64) -> bool) = match rule.op {
+            CmpOp::Lt => ("exclusiveMaximum", |new, old| new < old),
+            CmpOp::Le => ("maximum", |new, old| new < old),
+            CmpOp::Gt => ("exclusiveMinimum", |new, old| new > old),
+            CmpOp::Ge => ("minimum", |new, old| new > old),
+        };
+        let old = schema.get(keyword).and_then(Value::as_f64);
+        if old.is_none_or(|old| tighter(bound, old)) {
+            schema.insert(keyword.to_owned(), number(field, bound));
+        }
+    }
+
+    if !relations.is_empty() {
+        schema.insert(RELATIONS_KEYWORD.to_owned(), Value::Array(relations));
+    }
+    Value::Object(schema)
+}
+
+fn json_type(field: &FieldContract) -> Option<&'static str> {
+    if field.is_integer() {
+        Some("integer")
+    } else {
+        match field.ty {
+            "f32" | "f64" => Some("number"),
+            "bool" => Some("boolean"),
+            "String" | "&str" => Some("string"),
+            _ => None,
+        }
+    }
+}
+
+fn number(field: &FieldContract, value: f64) -> Value {
+    if field.is_integer() && value.fract() == 0.0 {
+        json!(value as i64)
+    } else {
+        json!(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::DistillationColumn;
+
+    #[test]
+    fn bounds_and_relations_are_exported() {
+        let schema = json_schema::<DistillationColumn>();
+        assert_eq!(schema["title"], "DistillationColumn");
+        assert_eq!(schema["required"].as_array().unwrap().len(), 3);
+
+        let props = &schema["properties"];
+        assert_eq!(props["trays"], json!({"type": "integer", "minimum": 3}));
+        assert_eq!(
+            props["distiliate_to_feed_ratio"],
+            json!({"type": "number", "exclusiveMinimum": 0.0, "exclusiveMaximum": 1.0})
+        );
+        assert_eq!(
+            props["feed_place"],
+            json!({
+                "type": "integer",
+                "minimum": 1,
+                RELATIONS_KEYWORD: [{"rule": "feed_place < trays", "op": "<", "rhs": "trays"}],
+            })
+        );
+    }
+}
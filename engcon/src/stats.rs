@@ -0,0 +1,408 @@
+//! Aggregate statistics of many validations, e.g. which contracts reject the most cases.
+
+use std::{collections::BTreeMap, fmt::Display};
+
+use crate::ValidationReport;
+
+/// Counts the validated records and the passes and failures per rule id.
+///
+/// The derive macro generates `validate_with_stats` that records every rule of the type, i.e.
+/// the passes as well. The rules of nested fields are recorded with the field as prefix, e.g.
+/// `feed.flow > 0.0`, if their type implements [Contracts](crate::Contracts).
+///
+/// Reports that are [recorded](Self::record) without the rules only list the failures, such a
+/// report counts as a pass of every rule that failed in another record. Rules that never failed
+/// are unknown then. Errors without a rule id, e.g. of manual [Validator](crate::Validator)
+/// implementations, are counted as [unattributed](Self::unattributed).
+///
+/// The statistics are printed as a summary table by [Display] and serialized to JSON with the
+/// `serde` feature.
+///
+/// ```
+/// use engcon::*;
+/// use engcon_macros::Validatable;
+///
+/// #[derive(Debug, Validatable)]
+/// struct DistillationColumn {
+///     #[validate_value(x >= 3)]
+///     trays: i32,
+///     #[validate_value(x < trays, x >= 1)]
+///     feed_place: i32,
+/// }
+///
+/// let mut stats = ValidationStats::new();
+/// for feed_place in 1..=10 {
+///     let _ = DistillationColumn { trays: 5, feed_place }.validate_with_stats(&mut stats);
+/// }
+/// assert_eq!(stats.rule("feed_place < trays"), RuleCounts { passed: 4, failed: 6 });
+/// assert_eq!(stats.failure_rate("feed_place < trays"), 0.6);
+/// println!("{}", stats);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationStats {
+    records: usize,
+    invalid: usize,
+    /// the number of records without the list of their rules
+    unlisted: usize,
+    rules: BTreeMap<String, Tally>,
+    unattributed: usize,
+}
+
+/// The number of records that passed and failed a rule
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct RuleCounts {
+    pub passed: usize,
+    pub failed: usize,
+}
+
+/// the counts of a rule, the failures in records without the list of their rules are counted
+/// apart to infer the passes of these records
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Tally {
+    passed: usize,
+    failed: usize,
+    failed_unlisted: usize,
+}
+
+impl ValidationStats {
+    /// generates empty statistics
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counts the record and the errors of its report, the record passed every rule without
+    /// an error
+    pub fn record(&mut self, report: &ValidationReport) {
+        self.unlisted += 1;
+        self.count(report, true);
+    }
+
+    fn count(&mut self, report: &ValidationReport, unlisted: bool) {
+        self.records += 1;
+        if !report.is_valid() {
+            self.invalid += 1;
+        }
+        for err in report.errors() {
+            match err.rule() {
+                Some(rule) => {
+                    let tally = self.rules.entry(rule.to_owned()).or_default();
+                    if unlisted {
+                        tally.failed_unlisted += 1;
+                    } else {
+                        tally.failed += 1;
+                    }
+                }
+                None => self.unattributed += 1,
+            }
+        }
+    }
+
+    /// Counts the record and the errors of its report, every rule in `rules` without an error
+    /// in the report counts as passed
+    pub fn record_rules<'a>(
+        &mut self,
+        rules: impl IntoIterator<Item = &'a str>,
+        report: &ValidationReport,
+    ) {
+        self.count(report, false);
+        for rule in rules {
+            if !report.errors().iter().any(|err| err.rule() == Some(rule)) {
+                self.rules.entry(rule.to_owned()).or_default().passed += 1;
+            }
+        }
+    }
+
+    /// adds the counts of other statistics, e.g. of another part of a batch
+    pub fn merge(&mut self, other: &ValidationStats) {
+        self.records += other.records;
+        self.invalid += other.invalid;
+        self.unlisted += other.unlisted;
+        self.unattributed += other.unattributed;
+        for (rule, tally) in &other.rules {
+            let entry = self.rules.entry(rule.clone()).or_default();
+            entry.passed += tally.passed;
+            entry.failed += tally.failed;
+            entry.failed_unlisted += tally.failed_unlisted;
+        }
+    }
+
+    /// the number of validated records
+    pub fn records(&self) -> usize {
+        self.records
+    }
+
+    /// the number of valid records
+    pub fn valid(&self) -> usize {
+        self.records - self.invalid
+    }
+
+    /// the number of invalid records
+    pub fn invalid(&self) -> usize {
+        self.invalid
+    }
+
+    /// the counts of the rule with the given id
+    pub fn rule(&self, rule: &str) -> RuleCounts {
+        self.rules
+            .get(rule)
+            .map(|tally| self.counts(tally))
+            .unwrap_or_default()
+    }
+
+    /// the counts of every recorded rule, sorted by the rule id
+    pub fn rules(&self) -> impl Iterator<Item = (&str, RuleCounts)> {
+        self.rules
+            .iter()
+            .map(|(rule, tally)| (rule.as_str(), self.counts(tally)))
+    }
+
+    fn counts(&self, tally: &Tally) -> RuleCounts {
+        RuleCounts {
+            passed: tally.passed + self.unlisted.saturating_sub(tally.failed_unlisted),
+            failed: tally.failed + tally.failed_unlisted,
+        }
+    }
+
+    /// how often the rule with the given id failed
+    pub fn failures(&self, rule: &str) -> usize {
+        self.rule(rule).failed
+    }
+
+    /// the share of the checks of the rule that failed, e.g. `0.6` for 60%
+    pub fn failure_rate(&self, rule: &str) -> f64 {
+        let counts = self.rule(rule);
+        let checks = counts.passed + counts.failed;
+        if checks == 0 {
+            return 0.0;
+        }
+        counts.failed as f64 / checks as f64
+    }
+
+    /// the number of errors without a rule id
+    pub fn unattributed(&self) -> usize {
+        self.unattributed
+    }
+
+    /// the failure counts of every failed rule, sorted by the rule id
+    pub fn failures_per_rule(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.rules()
+            .filter(|(_, counts)| counts.failed > 0)
+            .map(|(rule, counts)| (rule, counts.failed))
+    }
+
+    /// the `n` rules that failed most often, ties are sorted by the rule id
+    pub fn most_common(&self, n: usize) -> Vec<(&str, usize)> {
+        let mut failures: Vec<_> = self.failures_per_rule().collect();
+        failures.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        failures.truncate(n);
+        failures
+    }
+}
+
+impl Display for ValidationStats {
+    /// a summary table of every rule, the rules that failed most often first
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} records, {} valid, {} invalid",
+            self.records,
+            self.valid(),
+            self.invalid
+        )?;
+
+        let mut rules: Vec<_> = self.rules().collect();
+        rules.sort_by_key(|(_, counts)| std::cmp::Reverse(counts.failed));
+        let width = rules
+            .iter()
+            .map(|(rule, _)| rule.len())
+            .chain(["RULE".len()])
+            .max()
+            .unwrap_or_default();
+        write!(
+            f,
+            "{:width$}  {:>8}  {:>8}  {:>8}",
+            "RULE", "PASSED", "FAILED", "FAILED %"
+        )?;
+        for (rule, counts) in rules {
+            write!(
+                f,
+                "\n{:width$}  {:>8}  {:>8}  {:>8.1}",
+                rule,
+                counts.passed,
+                counts.failed,
+                100.0 * self.failure_rate(rule)
+            )?;
+        }
+        if self.unattributed > 0 {
+            write!(f, "\n{} errors without a rule", self.unattributed)?;
+        }
+        Ok(())
+    }
+}
+
+/// The schema is stable: the counts of the records and a list of the rules with their counts
+/// and failure rate, the rules that failed most often first.
+#[cfg(feature = "serde")]
+impl serde::Serialize for ValidationStats {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        #[derive(serde::Serialize)]
+        struct Rule<'a> {
+            rule: &'a str,
+            passed: usize,
+            failed: usize,
+            failure_rate: f64,
+        }
+
+        let mut rules: Vec<_> = self
+            .rules()
+            .map(|(rule, counts)| Rule {
+                rule,
+                passed: counts.passed,
+                failed: counts.failed,
+                failure_rate: self.failure_rate(rule),
+            })
+            .collect();
+        rules.sort_by_key(|rule| std::cmp::Reverse(rule.failed));
+
+        let mut s = serializer.serialize_struct("ValidationStats", 5)?;
+        s.serialize_field("records", &self.records)?;
+        s.serialize_field("valid", &self.valid())?;
+        s.serialize_field("invalid", &self.invalid)?;
+        s.serialize_field("unattributed", &self.unattributed)?;
+        s.serialize_field("rules", &rules)?;
+        s.end()
+    }
+}
+
+impl<'a> FromIterator<&'a ValidationReport> for ValidationStats {
+    fn from_iter<I: IntoIterator<Item = &'a ValidationReport>>(reports: I) -> Self {
+        let mut stats = ValidationStats::new();
+        for report in reports {
+            stats.record(report);
+        }
+        stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_fixtures::DistillationColumn;
+    use crate::*;
+
+    #[test]
+    fn failures_are_counted_per_rule() {
+        let reports: Vec<_> = [(10, 5), (10, 12), (2, 12), (10, 0)]
+            .into_iter()
+            .map(|(trays, feed_place)| DistillationColumn::new(trays, feed_place).validate_all())
+            .collect();
+        let stats: ValidationStats = reports.iter().collect();
+
+        assert_eq!((stats.records(), stats.valid(), stats.invalid()), (4, 1, 3));
+        assert_eq!(stats.failures("feed_place < trays"), 2);
+        assert_eq!(stats.failure_rate("feed_place < trays"), 0.5);
+        assert_eq!(stats.failure_rate("feed_place >= 1"), 0.25);
+        // rules that never failed are unknown without the list of the rules
+        assert_eq!(stats.rules().count(), 3);
+        assert_eq!(
+            stats.most_common(2),
+            [("feed_place < trays", 2), ("feed_place >= 1", 1)]
+        );
+
+        let mut twice = stats.clone();
+        twice.merge(&stats);
+        assert_eq!(twice.records(), 8);
+        assert_eq!(twice.failures("trays >= 3"), 2);
+    }
+
+    #[test]
+    fn generated_code_counts_passes_and_failures() {
+        let mut stats = ValidationStats::new();
+        for (trays, feed_place) in [(10, 5), (10, 12), (2, 12), (10, 0)] {
+            let column = DistillationColumn::new(trays, feed_place);
+            let result = column.validate_with_stats(&mut stats);
+            assert_eq!(result, column.validate());
+        }
+
+        assert_eq!(
+            stats.rule("trays >= 3"),
+            RuleCounts {
+                passed: 3,
+                failed: 1
+            }
+        );
+        assert_eq!(
+            stats.rule("feed_place < trays"),
+            RuleCounts {
+                passed: 2,
+                failed: 2
+            }
+        );
+        assert_eq!(stats.rules().count(), 5);
+
+        let table = stats.to_string();
+        let mut lines = table.lines();
+        assert_eq!(lines.next(), Some("4 records, 1 valid, 3 invalid"));
+        assert_eq!(
+            lines.next(),
+            Some("RULE                              PASSED    FAILED  FAILED %")
+        );
+        assert_eq!(
+            lines.next(),
+            Some("feed_place < trays                     2         2      50.0")
+        );
+    }
+
+    #[derive(Debug, Clone, engcon_macros::Validatable)]
+    struct Plant {
+        #[validate_value(x > 0.0)]
+        flow: f64,
+        #[validate(nested)]
+        column: DistillationColumn,
+    }
+
+    #[test]
+    fn nested_rules_are_counted_with_their_field() {
+        let mut stats = ValidationStats::new();
+        for feed_place in [5, 12] {
+            let plant = Plant {
+                flow: 1.0,
+                column: DistillationColumn::new(10, feed_place),
+            };
+            let _ = plant.validate_with_stats(&mut stats);
+        }
+        let _ = DistillationColumn::new(10, 5).validate_with_stats(&mut stats);
+
+        assert_eq!(
+            stats.rule("column.feed_place < trays"),
+            RuleCounts {
+                passed: 1,
+                failed: 1
+            }
+        );
+        assert_eq!(stats.rule("column.trays >= 3").passed, 2);
+        assert_eq!(stats.failure_rate("column.feed_place < trays"), 0.5);
+        assert_eq!(stats.failure_rate("flow > 0.0"), 0.0);
+        assert_eq!(stats.records(), 3);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn stats_are_exported_to_json() {
+        let mut stats = ValidationStats::new();
+        let _ = DistillationColumn::new(10, 12).validate_with_stats(&mut stats);
+
+        let json = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["invalid"], 1);
+        assert_eq!(
+            json["rules"][0],
+            serde_json::json!({
+                "rule": "feed_place < trays",
+                "passed": 0,
+                "failed": 1,
+                "failure_rate": 1.0,
+            })
+        );
+    }
+}
@@ -0,0 +1,157 @@
+//! [proptest](https://docs.rs/proptest) strategies derived from the contracts of a type.
+//!
+//! With the `proptest` feature [Validated] implements `Arbitrary` for types deriving
+//! [Validatable](crate::Validatable) with primitive numeric fields, i.e. `any::<Validated<T>>()`
+//! generates only valid values. [near_boundary] generates values that violate a rule by a
+//! small distance, e.g. to test the error handling.
+//!
+//! ```
+//! use engcon::*;
+//! use engcon_macros::Validatable;
+//! use proptest::prelude::*;
+//!
+//! #[derive(Debug, Clone, Default, Validatable)]
+//! pub struct DistillationColumn {
+//!     #[validate_value(x >= 3, x <= 60)]
+//!     pub trays: i32,
+//!     #[validate_value(x < trays, x >= 1)]
+//!     pub feed_place: i32,
+//! }
+//!
+//! proptest! {
+//!     fn feed_is_above_the_sump(dc in any::<Validated<DistillationColumn>>()) {
+//!         prop_assert!(dc.feed_place < dc.trays);
+//!     }
+//!
+//!     fn invalid_columns_are_rejected(dc in strategy::near_boundary::<DistillationColumn>()) {
+//!         prop_assert!(dc.validate().is_err());
+//!     }
+//! }
+//! # feed_is_above_the_sump();
+//! # invalid_columns_are_rejected();
+//! ```
+
+use std::fmt::Debug;
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use crate::{Contracts, Sample, SampleRange, Validated, Validator};
+
+/// Generates valid values, shrinks towards the lower bounds of the fields.
+///
+/// The fields are drawn in dependency order, unbounded sides are limited to
+/// [SampleRange::DEFAULT_SPAN] values.
+pub fn valid<T>() -> impl Strategy<Value = Validated<T>>
+where
+    T: Sample + Validator + Contracts + Default + Debug + 'static,
+{
+    vec(0.0..=1.0f64, T::CONTRACTS.len())
+        .prop_map(|units| {
+            let mut value = T::default();
+            value.sample_fields(&(), &mut |range| {
+                range.interpolate(units[index_of::<T>(range)], SampleRange::DEFAULT_SPAN)
+            });
+            value
+        })
+        .prop_filter_map("the rules of a field contradict each other", |value| {
+            value.try_into_validated().ok()
+        })
+}
+
+/// Generates invalid values where a single field lies 1 to 3 steps beyond a bound of its
+/// valid range, e.g. `feed_place == trays` for `x < trays`. Fields that depend on it may
+/// violate their rules, too, e.g. `feed_place` if `trays` is below its lower bound.
+///
+/// A step is 1 for integers and `1e-3` times the bound, at least `1e-3`, for floats. The
+/// strategy shrinks towards the first field and its lower bound.
+pub fn near_boundary<T>() -> impl Strategy<Value = T>
+where
+    T: Sample + Validator + Contracts + Default + Debug + 'static,
+{
+    let n = T::CONTRACTS.len();
+    (vec(0.0..=1.0f64, n), 0..n.max(1), any::<bool>(), 1..=3u32)
+        .prop_map(|(units, target, upper, steps)| {
+            let mut value = T::default();
+            value.sample_fields(&(), &mut |range| {
+                let i = index_of::<T>(range);
+                if i == target {
+                    beyond(range, upper, steps)
+                        .unwrap_or_else(|| range.interpolate(units[i], SampleRange::DEFAULT_SPAN))
+                } else {
+                    range.interpolate(units[i], SampleRange::DEFAULT_SPAN)
+                }
+            });
+            value
+        })
+        .prop_filter("the value is valid", |value| value.validate().is_err())
+}
+
+impl<T> Arbitrary for Validated<T>
+where
+    T: Sample + Validator + Contracts + Default + Debug + 'static,
+{
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        valid().boxed()
+    }
+}
+
+fn index_of<T: Contracts>(range: &SampleRange) -> usize {
+    T::CONTRACTS
+        .iter()
+        .position(|c| c.field == range.field)
+        .expect("sampled fields have contracts")
+}
+
+/// gets a value `steps` steps beyond the upper or lower bound, the other bound is used if
+/// the side is unbounded
+fn beyond(range: &SampleRange, upper: bool, steps: u32) -> Option<f64> {
+    let upper = match (range.lower.is_finite(), range.upper.is_finite()) {
+        (false, false) => return None,
+        (true, false) => false,
+        (false, true) => true,
+        (true, true) => upper,
+    };
+    let (bound, strict) = if upper {
+        (range.upper, range.upper_strict)
+    } else {
+        (range.lower, range.lower_strict)
+    };
+
+    let steps = if strict { steps - 1 } else { steps } as f64;
+    let distance = if range.integer {
+        steps
+    } else {
+        steps * 1e-3 * bound.abs().max(1.0)
+    };
+    Some(if upper {
+        bound + distance
+    } else {
+        bound - distance
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::DistillationColumn;
+    use crate::*;
+
+    proptest! {
+        #[test]
+        fn arbitrary_values_are_valid(dc in any::<Validated<DistillationColumn>>()) {
+            prop_assert!(dc.validate().is_ok());
+            prop_assert!(dc.feed_place < dc.trays);
+        }
+
+        #[test]
+        fn near_boundary_values_are_close_to_valid(dc in near_boundary::<DistillationColumn>()) {
+            let violations = dc.violations();
+            prop_assert!(violations.iter().any(|v| *v > 0.0));
+            prop_assert!(violations.iter().all(|v| *v <= 3.0));
+        }
+    }
+}
@@ -0,0 +1,257 @@
+//! Batch validation of tabular data, e.g. design case matrices exported from a spreadsheet as
+//! CSV.
+//!
+//! Every row is deserialized into `T` by serde and validated. The valid rows are returned as
+//! [Validated] records, the errors are collected in an error table with the row and the column
+//! of every error.
+//!
+//! ```
+//! use engcon::*;
+//! use engcon_macros::Validatable;
+//!
+//! #[derive(Debug, serde::Deserialize, Validatable)]
+//! struct DistillationColumn {
+//!     #[validate_value(x >= 3)]
+//!     trays: i32,
+//!     #[validate_value(x < trays, x >= 1)]
+//!     feed_place: i32,
+//! }
+//!
+//! let csv = "trays,feed_place\n20,10\n20,25\nmany,10\n";
+//! let batch = tabular::validate_csv::<DistillationColumn, _>(csv.as_bytes()).unwrap();
+//! assert_eq!(batch.valid().len(), 1);
+//!
+//! let errors = batch.errors();
+//! assert_eq!((errors[0].row, errors[0].column.as_deref()), (3, Some("feed_place")));
+//! assert_eq!(errors[0].rule.as_deref(), Some("feed_place < trays"));
+//! assert_eq!((errors[1].row, errors[1].column.as_deref()), (4, Some("trays")));
+//! ```
+
+use std::{fmt::Display, io::Read, path::Path};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{Validated, ValidationError, Validator};
+
+/// An entry of the error table, i.e. a single error in a row
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RowError {
+    /// the row in the file, i.e. the row in a spreadsheet with the header in row 1
+    pub row: usize,
+    /// the column of the violated rule or of the cell that cannot be read, if known
+    pub column: Option<String>,
+    /// the id of the violated rule, `None` if the row cannot be read
+    pub rule: Option<String>,
+    /// the value of the cell as it was validated
+    pub value: Option<String>,
+    pub message: String,
+}
+
+/// The result of a batch validation: the valid records and an error table
+#[derive(Debug, Clone, PartialEq)]
+pub struct Batch<T> {
+    valid: Vec<(usize, Validated<T>)>,
+    errors: Vec<RowError>,
+    rows: usize,
+}
+
+impl<T> Batch<T> {
+    /// the valid records with their row
+    pub fn valid(&self) -> &[(usize, Validated<T>)] {
+        &self.valid
+    }
+
+    /// the error table, sorted by row and in the order of the rules within a row
+    pub fn errors(&self) -> &[RowError] {
+        &self.errors
+    }
+
+    /// the number of rows without the header
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// true if every row is valid
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// splits the batch into the valid records and the error table
+    pub fn into_parts(self) -> (Vec<Validated<T>>, Vec<RowError>) {
+        let valid = self.valid.into_iter().map(|(_, value)| value).collect();
+        (valid, self.errors)
+    }
+
+    /// writes the error table as CSV with the columns `row`, `column`, `rule`, `value` and
+    /// `message`
+    pub fn write_errors<W: std::io::Write>(&self, writer: W) -> csv::Result<()> {
+        let mut writer = csv::Writer::from_writer(writer);
+        for err in &self.errors {
+            writer.serialize(err)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Validates every row of a CSV with a header, the header gives the names of the fields.
+///
+/// Rows that cannot be deserialized end up in the error table, errors of the reader like
+/// invalid UTF-8 stop the validation.
+pub fn validate_csv<T, R>(reader: R) -> csv::Result<Batch<T>>
+where
+    T: DeserializeOwned + Validator,
+    R: Read,
+{
+    // rows with missing cells end up in the error table instead of stopping the validation
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(reader);
+    let headers = reader.headers()?.clone();
+
+    let mut batch = Batch {
+        valid: vec![],
+        errors: vec![],
+        rows: 0,
+    };
+    let mut record = csv::StringRecord::new();
+    while reader.read_record(&mut record)? {
+        batch.rows += 1;
+        let row = record
+            .position()
+            .map_or(batch.rows + 1, |p| p.line() as usize);
+        let value = match record.deserialize::<T>(Some(&headers)) {
+            Ok(value) => value,
+            Err(err) => {
+                batch.errors.push(read_error(row, &headers, err));
+                continue;
+            }
+        };
+
+        let report = value.validate_all();
+        if report.is_valid() {
+            // just checked if that is validated...
+            let valid = unsafe { Validated::new_unchecked(value) };
+            batch.valid.push((row, valid));
+        } else {
+            let errors = report.errors().iter().map(|err| RowError::new(row, err));
+            batch.errors.extend(errors);
+        }
+    }
+    Ok(batch)
+}
+
+/// validates every row of a CSV file, see [validate_csv]
+pub fn validate_csv_file<T: DeserializeOwned + Validator>(
+    path: impl AsRef<Path>,
+) -> csv::Result<Batch<T>> {
+    validate_csv(std::fs::File::open(path)?)
+}
+
+impl RowError {
+    /// an entry for a validation error, the column is the first segment of the field path
+    pub fn new(row: usize, err: &ValidationError) -> Self {
+        let violation = err.violation();
+        RowError {
+            row,
+            column: violation.map(|v| v.field.split('.').next().unwrap_or(&v.field).to_owned()),
+            rule: violation.map(|v| v.rule.clone()),
+            value: violation.map(|v| v.value.clone()),
+            message: err.msg().to_owned(),
+        }
+    }
+}
+
+/// an entry for a row that cannot be deserialized
+fn read_error(row: usize, headers: &csv::StringRecord, err: csv::Error) -> RowError {
+    let column = match err.kind() {
+        csv::ErrorKind::Deserialize { err, .. } => err
+            .field()
+            .and_then(|i| headers.get(i as usize))
+            .map(str::to_owned),
+        _ => None,
+    };
+    let message = match err.into_kind() {
+        csv::ErrorKind::Deserialize { err, .. } => err.to_string(),
+        kind => format!("{:?}", kind),
+    };
+    RowError {
+        row,
+        column,
+        rule: None,
+        value: None,
+        message,
+    }
+}
+
+impl Display for RowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "row {}", self.row)?;
+        if let Some(column) = &self.column {
+            write!(f, ", column '{}'", column)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::DistillationColumn;
+
+    const CSV: &str = "\
+trays, feed_place, distiliate_to_feed_ratio
+20, 10, 0.5
+2, 0, 0.5
+20, 10
+20, 10, 1.5
+";
+
+    #[test]
+    fn rows_are_split_into_valid_records_and_errors() {
+        let batch = validate_csv::<DistillationColumn, _>(CSV.as_bytes()).unwrap();
+        assert_eq!(batch.rows(), 4);
+        assert!(!batch.is_valid());
+        assert_eq!(batch.valid()[0].0, 2);
+        assert_eq!(batch.valid()[0].1.trays, 20);
+
+        let table: Vec<_> = batch
+            .errors()
+            .iter()
+            .map(|e| (e.row, e.column.as_deref(), e.rule.as_deref()))
+            .collect();
+        assert_eq!(
+            table,
+            [
+                (3, Some("trays"), Some("trays >= 3")),
+                (3, Some("feed_place"), Some("feed_place >= 1")),
+                (4, None, None),
+                (
+                    5,
+                    Some("distiliate_to_feed_ratio"),
+                    Some("distiliate_to_feed_ratio < 1.0")
+                ),
+            ]
+        );
+        assert_eq!(batch.errors()[3].value.as_deref(), Some("1.5"));
+
+        let (valid, errors) = batch.into_parts();
+        assert_eq!((valid.len(), errors.len()), (1, 4));
+    }
+
+    #[test]
+    fn error_table_is_written_as_csv() {
+        let batch = validate_csv::<DistillationColumn, _>(CSV.as_bytes()).unwrap();
+        let mut table = vec![];
+        batch.write_errors(&mut table).unwrap();
+        let table = String::from_utf8(table).unwrap();
+        let mut lines = table.lines();
+        assert_eq!(lines.next(), Some("row,column,rule,value,message"));
+        assert_eq!(
+            lines.next(),
+            Some("3,trays,trays >= 3,2,value=2: 'trays' >= '3'")
+        );
+        assert_eq!(lines.count(), 3);
+    }
+}
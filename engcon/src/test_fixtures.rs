@@ -0,0 +1,24 @@
+//! Types that are shared by the unit tests of the modules.
+
+/// the distillation column of the examples
+#[derive(Debug, Clone, Default, PartialEq, engcon_macros::Validatable)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct DistillationColumn {
+    #[validate_value(x >= 3)]
+    pub trays: i32,
+    #[validate_value(x < trays, x >= 1)]
+    pub feed_place: i32,
+    #[validate_value(x > 0.0, x < 1.0)]
+    pub distiliate_to_feed_ratio: f32,
+}
+
+impl DistillationColumn {
+    /// a column with a valid ratio of distillate to feed
+    pub fn new(trays: i32, feed_place: i32) -> Self {
+        DistillationColumn {
+            trays,
+            feed_place,
+            distiliate_to_feed_ratio: 0.5,
+        }
+    }
+}
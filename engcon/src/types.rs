@@ -0,0 +1,449 @@
+//! Validated newtypes for quantities that appear in most engineering models.
+//!
+//! Every type implements [Validator] and [Contracts], `new()` gives a [Validated] instance.
+//! Arithmetic is only implemented on [Validated] values where the result keeps the
+//! invariant, e.g. the product of two fractions is a fraction. Use them as fields with
+//! `#[validate(nested)]` to validate them together with the surrounding struct:
+//!
+//! ```
+//! use engcon::*;
+//! use engcon::types::{Fraction, Kelvin};
+//! use engcon_macros::Validatable;
+//!
+//! #[derive(Debug, Clone, Validatable)]
+//! pub struct Feed {
+//!     #[validate(nested)]
+//!     pub quality: Fraction,
+//!     #[validate(nested)]
+//!     pub temperature: Kelvin,
+//! }
+//!
+//! let feed = Feed { quality: Fraction(1.2), temperature: Kelvin(350.0) };
+//! let err = feed.validate().unwrap_err();
+//! assert_eq!(err.field(), Some("quality.0"));
+//! ```
+
+use std::fmt::Display;
+use std::ops::{Add, Mul};
+
+use crate::{
+    Basis, CmpOp, Contracts, FieldContract, RuleContract, RuleViolation, Validated,
+    ValidationError, ValidationReport, Validator,
+};
+
+/// defines a newtype of `f64` whose contract consists of constant bounds
+macro_rules! bounded_newtype {
+    (
+        $(#[$doc:meta])*
+        $name:ident, unit: $unit:expr, rules: [$(($op:ident, $symbol:literal, $rhs:literal, $bound:expr)),+]
+    ) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+        #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+        #[cfg_attr(feature = "serde", serde(transparent))]
+        pub struct $name(pub f64);
+
+        impl $name {
+            /// generates a validated instance, fails if the value violates a rule
+            pub fn new(value: f64) -> Result<Validated<Self>, ValidationError> {
+                $name(value).try_into_validated()
+            }
+        }
+
+        impl Contracts for $name {
+            const TYPE_NAME: &'static str = stringify!($name);
+            const FIELDS: &'static [&'static str] = &["0"];
+            const CONTRACTS: &'static [FieldContract] = &[FieldContract {
+                field: "0",
+                ty: "f64",
+                unit: $unit,
+                rules: &[$(RuleContract {
+                    id: concat!("0 ", $symbol, " ", $rhs),
+                    field: "0",
+                    op: CmpOp::$op,
+                    rhs: $rhs,
+                    bound: Some($bound),
+                    sibling: None,
+                    uses_context: false,
+                }),+],
+            }];
+        }
+
+        impl Validator for $name {
+            fn validate(&self) -> Result<(), ValidationError> {
+                match Self::rules().find(|rule| !holds(rule, self.0)) {
+                    Some(rule) => Err(violation(Self::TYPE_NAME, rule.field, rule, self.0)),
+                    None => Ok(()),
+                }
+            }
+
+            fn validate_all(&self) -> ValidationReport {
+                let mut report = ValidationReport::new(Self::TYPE_NAME.to_owned());
+                for rule in Self::rules().filter(|rule| !holds(rule, self.0)) {
+                    report.push(violation(Self::TYPE_NAME, rule.field, rule, self.0));
+                }
+                report
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+    };
+}
+
+bounded_newtype!(
+    /// A fraction in `[0, 1]`, e.g. the vapor fraction of a feed
+    Fraction,
+    unit: None,
+    rules: [(Ge, ">=", "0.0", 0.0), (Le, "<=", "1.0", 1.0)]
+);
+
+bounded_newtype!(
+    /// A probability in `[0, 1]`, e.g. the availability of a pump
+    Probability,
+    unit: None,
+    rules: [(Ge, ">=", "0.0", 0.0), (Le, "<=", "1.0", 1.0)]
+);
+
+bounded_newtype!(
+    /// A percentage in `[0, 100]`
+    Percent,
+    unit: Some("%"),
+    rules: [(Ge, ">=", "0.0", 0.0), (Le, "<=", "100.0", 100.0)]
+);
+
+bounded_newtype!(
+    /// A strictly positive number, e.g. a flow rate
+    PositiveF64,
+    unit: None,
+    rules: [(Gt, ">", "0.0", 0.0)]
+);
+
+bounded_newtype!(
+    /// A number that is zero or positive, e.g. a heat duty
+    NonNegative,
+    unit: None,
+    rules: [(Ge, ">=", "0.0", 0.0)]
+);
+
+bounded_newtype!(
+    /// An absolute temperature in K
+    Kelvin,
+    unit: Some("K"),
+    rules: [(Gt, ">", "0.0", 0.0)]
+);
+
+/// checks a rule of a newtype, `NaN` violates every rule
+fn holds(rule: &RuleContract, value: f64) -> bool {
+    rule.bound.is_some_and(|bound| rule.op.eval(value, bound))
+}
+
+/// generates the error of a violated rule of the given field like the derive macro does
+fn violation(src: &str, field: &str, rule: &RuleContract, value: f64) -> ValidationError {
+    ValidationError::new(
+        format!("value={}: '{}' {} '{}'", value, field, rule.op, rule.rhs),
+        src.to_owned(),
+    )
+    .with_violation(RuleViolation {
+        field: field.to_owned(),
+        rule: format!("{} {} {}", field, rule.op, rule.rhs),
+        op: rule.op,
+        bound: rule.rhs.to_owned(),
+        value: value.to_string(),
+        bound_num: rule.bound,
+        value_num: Some(value),
+    })
+}
+
+impl Validated<Fraction> {
+    /// `1 - x`, e.g. the liquid fraction of a feed with the vapor fraction `x`
+    pub fn complement(&self) -> Self {
+        // SAFETY: 1 - x lies in [0, 1] for x in [0, 1]
+        unsafe { Validated::new_unchecked(Fraction(1.0 - self.0)) }
+    }
+
+    /// the fraction in percent
+    pub fn to_percent(&self) -> Validated<Percent> {
+        // SAFETY: 100 * x lies in [0, 100] for x in [0, 1]
+        unsafe { Validated::new_unchecked(Percent(self.0 * 100.0)) }
+    }
+}
+
+impl Mul for Validated<Fraction> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        // SAFETY: the product of two values in [0, 1] lies in [0, 1]
+        unsafe { Validated::new_unchecked(Fraction(self.0 * rhs.0)) }
+    }
+}
+
+impl Validated<Probability> {
+    /// the probability of the complementary event, `1 - p`
+    pub fn complement(&self) -> Self {
+        // SAFETY: 1 - p lies in [0, 1] for p in [0, 1]
+        unsafe { Validated::new_unchecked(Probability(1.0 - self.0)) }
+    }
+}
+
+/// the probability that two independent events occur together
+impl Mul for Validated<Probability> {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        // SAFETY: the product of two values in [0, 1] lies in [0, 1]
+        unsafe { Validated::new_unchecked(Probability(self.0 * rhs.0)) }
+    }
+}
+
+impl Validated<Percent> {
+    /// the percentage as fraction
+    pub fn to_fraction(&self) -> Validated<Fraction> {
+        // SAFETY: x / 100 lies in [0, 1] for x in [0, 100]
+        unsafe { Validated::new_unchecked(Fraction(self.0 / 100.0)) }
+    }
+}
+
+impl Add for Validated<PositiveF64> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        // SAFETY: the sum of two positive numbers is positive, it may overflow to infinity
+        unsafe { Validated::new_unchecked(PositiveF64(self.0 + rhs.0)) }
+    }
+}
+
+impl Add for Validated<NonNegative> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        // SAFETY: the sum of two non-negative numbers is non-negative
+        unsafe { Validated::new_unchecked(NonNegative(self.0 + rhs.0)) }
+    }
+}
+
+impl Kelvin {
+    /// generates a validated temperature from a value in °C
+    pub fn from_celsius(celsius: f64) -> Result<Validated<Self>, ValidationError> {
+        Kelvin::new(celsius + 273.15)
+    }
+
+    /// the temperature in °C
+    pub fn to_celsius(&self) -> f64 {
+        self.0 - 273.15
+    }
+}
+
+/// The mole fractions of a mixture, every fraction is in `[0, 1]` and they sum up to 1
+/// within [MoleFractionVec::TOLERANCE]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct MoleFractionVec(pub Vec<f64>);
+
+impl MoleFractionVec {
+    /// the allowed deviation of the sum from 1
+    pub const TOLERANCE: f64 = 1e-9;
+
+    /// generates a validated instance, fails if a fraction or the sum is invalid
+    pub fn new(fractions: Vec<f64>) -> Result<Validated<Self>, ValidationError> {
+        MoleFractionVec(fractions).try_into_validated()
+    }
+
+    /// Scales non-negative amounts, e.g. molar flows, to fractions that sum up to 1
+    pub fn normalized(amounts: Vec<f64>) -> Result<Validated<Self>, ValidationError> {
+        let sum = normalizing_sum("MoleFractionVec", &amounts)?;
+        Self::new(amounts.into_iter().map(|a| a / sum).collect())
+    }
+
+    /// the number of components
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// true if there are no components
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Validator for MoleFractionVec {
+    fn validate(&self) -> Result<(), ValidationError> {
+        self.validate_all().into_result()
+    }
+
+    fn validate_all(&self) -> ValidationReport {
+        let mut report = ValidationReport::new("MoleFractionVec".to_owned());
+        check_fractions(
+            &mut report,
+            &self.0,
+            Basis::Mole,
+            Self::TOLERANCE,
+            |i| format!("0[{}]", i),
+            "0",
+        );
+        report
+    }
+}
+
+/// The sum of amounts that are scaled to fractions, e.g. by [MoleFractionVec::normalized].
+///
+/// Fails if an amount is negative or the sum is not positive.
+pub(crate) fn normalizing_sum(src: &str, amounts: &[f64]) -> Result<f64, ValidationError> {
+    let sum: f64 = amounts.iter().sum();
+    if !amounts.iter().all(|a| *a >= 0.0) || sum <= 0.0 {
+        return Err(ValidationError::new(
+            format!(
+                "cannot normalize {:?}: the amounts must be non-negative with a positive sum",
+                amounts
+            ),
+            src.to_owned(),
+        ));
+    }
+    Ok(sum)
+}
+
+/// Checks the invariant of [MoleFractionVec] and [Composition](crate::Composition): every
+/// fraction is a [Fraction] and the fractions sum up to 1 within the tolerance.
+///
+/// `field` gives the name of the fraction at an index, `sum_field` the name of the fractions
+/// for the rule on the sum, e.g. `fractions sum <= 1.000000001` if the sum is too large.
+pub(crate) fn check_fractions(
+    report: &mut ValidationReport,
+    fractions: &[f64],
+    basis: Basis,
+    tolerance: f64,
+    field: impl Fn(usize) -> String,
+    sum_field: &str,
+) {
+    let src = report.src().to_owned();
+    for (i, x) in fractions.iter().enumerate() {
+        for rule in Fraction::rules().filter(|rule| !holds(rule, *x)) {
+            report.push(violation(&src, &field(i), rule, *x));
+        }
+    }
+
+    let sum: f64 = fractions.iter().sum();
+    // NaN compares false, i.e. it is not a valid sum
+    let sum_is_one = (sum - 1.0).abs() <= tolerance;
+    if !sum_is_one {
+        let (op, bound) = if sum < 1.0 {
+            (CmpOp::Ge, 1.0 - tolerance)
+        } else {
+            (CmpOp::Le, 1.0 + tolerance)
+        };
+        report.push(
+            ValidationError::new(
+                format!(
+                    "sum={}: the {:?} fractions must sum up to 1 within {}",
+                    sum, basis, tolerance
+                ),
+                src,
+            )
+            .with_violation(RuleViolation {
+                field: sum_field.to_owned(),
+                rule: format!("{} sum {} {}", sum_field, op, bound),
+                op,
+                bound: bound.to_string(),
+                value: sum.to_string(),
+                bound_num: Some(bound),
+                value_num: Some(sum),
+            }),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, engcon_macros::Validatable)]
+    struct Feed {
+        #[validate_value(x > 0.0)]
+        flow: f64,
+        #[validate(nested)]
+        quality: Fraction,
+        #[validate(nested)]
+        composition: MoleFractionVec,
+    }
+
+    #[test]
+    fn constructors_check_the_bounds() {
+        assert!(Fraction::new(0.5).is_ok());
+        assert!(Fraction::new(1.5).is_err());
+        assert!(Fraction::new(f64::NAN).is_err());
+        assert!(PositiveF64::new(0.0).is_err());
+        assert!(NonNegative::new(0.0).is_ok());
+        assert!(Percent::new(100.0).is_ok());
+        assert_eq!(Kelvin::new(-1.0).unwrap_err().rule(), Some("0 > 0.0"));
+        assert!(Kelvin::from_celsius(-300.0).is_err());
+    }
+
+    #[test]
+    fn arithmetic_keeps_the_invariant() {
+        let x = Fraction::new(0.25).unwrap();
+        assert_eq!(x.complement().0, 0.75);
+        assert_eq!((x.clone() * x.complement()).0, 0.1875);
+        assert_eq!(x.to_percent().to_fraction(), x);
+
+        let p = Probability::new(0.9).unwrap();
+        assert!(((p.clone() * p).0 - 0.81).abs() < 1e-12);
+
+        let q = PositiveF64::new(1.5).unwrap() + PositiveF64::new(2.5).unwrap();
+        assert_eq!(q.0, 4.0);
+        assert_eq!(Kelvin(300.0).to_celsius(), 300.0 - 273.15);
+    }
+
+    #[test]
+    fn mole_fractions_sum_up_to_one() {
+        assert!(MoleFractionVec::new(vec![0.2, 0.3, 0.5]).is_ok());
+        assert!(MoleFractionVec::new(vec![0.2, 0.3]).is_err());
+
+        let x = MoleFractionVec::normalized(vec![1.0, 3.0]).unwrap();
+        assert_eq!(x.0, [0.25, 0.75]);
+        assert!(MoleFractionVec::normalized(vec![-1.0, 3.0]).is_err());
+
+        let report = MoleFractionVec(vec![1.5, -0.5]).validate_all();
+        let fields: Vec<_> = report.errors().iter().filter_map(|e| e.field()).collect();
+        assert_eq!(fields, ["0[0]", "0[1]"]);
+    }
+
+    #[test]
+    fn newtypes_are_validated_as_nested_fields() {
+        let mut feed = Feed {
+            flow: 10.0,
+            quality: Fraction(0.4),
+            composition: MoleFractionVec(vec![0.5, 0.5]),
+        };
+        assert!(feed.validate().is_ok());
+
+        feed.quality = Fraction(1.4);
+        feed.composition = MoleFractionVec(vec![0.5, 0.6]);
+        let err = feed.validate().unwrap_err();
+        assert_eq!(err.src(), "Feed");
+        assert_eq!(err.field(), Some("quality.0"));
+        assert_eq!(err.rule(), Some("quality.0 <= 1.0"));
+        assert_eq!(err.msg(), "quality: value=1.4: '0' <= '1.0'");
+
+        let report = feed.validate_all();
+        assert_eq!(report.errors().len(), 2);
+        assert_eq!(report.errors()[1].src(), "Feed");
+        // the sum is attributed to the fractions like the rules of the fields
+        let sum = report.errors()[1].violation().unwrap();
+        assert_eq!(sum.field, "composition.0");
+        assert_eq!(sum.rule, "composition.0 sum <= 1.000000001");
+        assert_eq!((sum.op, sum.bound.as_str()), (CmpOp::Le, "1.000000001"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn validated_newtypes_are_checked_when_deserialized() {
+        let x: Validated<Fraction> = serde_json::from_str("0.5").unwrap();
+        assert_eq!(serde_json::to_string(&x).unwrap(), "0.5");
+        assert!(serde_json::from_str::<Validated<Fraction>>("1.5").is_err());
+        assert!(serde_json::from_str::<Validated<MoleFractionVec>>("[0.5, 0.5]").is_ok());
+    }
+}
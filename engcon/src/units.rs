@@ -0,0 +1,252 @@
+//! Conversion between common engineering units, e.g. to load inputs given in °C or psi
+//! into fields annotated with `#[unit("K")]` or `#[unit("bar")]`.
+//!
+//! Units are given by their symbol, e.g. `bar`, `°C` or `kmol/h`. Only units of the same
+//! [Dimension] can be converted into each other.
+
+use std::{error::Error, fmt::Display};
+
+use crate::Contracts;
+
+/// The physical dimension of a unit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dimension {
+    Temperature,
+    Pressure,
+    Length,
+    Volume,
+    Mass,
+    Time,
+    MassFlow,
+    MolarFlow,
+    Energy,
+    Power,
+}
+
+/// An error that occurs if a unit is unknown or the dimensions of two units differ
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConversionError {
+    UnknownUnit(String),
+    DimensionMismatch {
+        from: String,
+        to: String,
+    },
+    /// the field has no `#[unit(..)]` attribute or no contract
+    NoUnit {
+        ty: &'static str,
+        field: String,
+    },
+}
+
+/// a unit with its conversion to the SI unit of its dimension: `si = value * factor + offset`
+struct Unit {
+    symbols: &'static [&'static str],
+    dimension: Dimension,
+    factor: f64,
+    offset: f64,
+}
+
+const fn unit(symbols: &'static [&'static str], dimension: Dimension, factor: f64) -> Unit {
+    Unit {
+        symbols,
+        dimension,
+        factor,
+        offset: 0.0,
+    }
+}
+
+const UNITS: &[Unit] = &[
+    unit(&["K"], Dimension::Temperature, 1.0),
+    Unit {
+        symbols: &["°C", "degC"],
+        dimension: Dimension::Temperature,
+        factor: 1.0,
+        offset: 273.15,
+    },
+    Unit {
+        symbols: &["°F", "degF"],
+        dimension: Dimension::Temperature,
+        factor: 5.0 / 9.0,
+        offset: 273.15 - 32.0 * 5.0 / 9.0,
+    },
+    unit(&["Pa"], Dimension::Pressure, 1.0),
+    unit(&["kPa"], Dimension::Pressure, 1e3),
+    unit(&["MPa"], Dimension::Pressure, 1e6),
+    unit(&["mbar"], Dimension::Pressure, 1e2),
+    unit(&["bar"], Dimension::Pressure, 1e5),
+    unit(&["atm"], Dimension::Pressure, 101_325.0),
+    unit(&["psi"], Dimension::Pressure, 6_894.757_293_168),
+    unit(&["mmHg"], Dimension::Pressure, 133.322_387_415),
+    unit(&["m"], Dimension::Length, 1.0),
+    unit(&["mm"], Dimension::Length, 1e-3),
+    unit(&["cm"], Dimension::Length, 1e-2),
+    unit(&["km"], Dimension::Length, 1e3),
+    unit(&["in"], Dimension::Length, 0.0254),
+    unit(&["ft"], Dimension::Length, 0.3048),
+    unit(&["m3", "m³"], Dimension::Volume, 1.0),
+    unit(&["L", "l"], Dimension::Volume, 1e-3),
+    unit(&["kg"], Dimension::Mass, 1.0),
+    unit(&["g"], Dimension::Mass, 1e-3),
+    unit(&["t"], Dimension::Mass, 1e3),
+    unit(&["lb"], Dimension::Mass, 0.453_592_37),
+    unit(&["s"], Dimension::Time, 1.0),
+    unit(&["min"], Dimension::Time, 60.0),
+    unit(&["h"], Dimension::Time, 3600.0),
+    unit(&["kg/s"], Dimension::MassFlow, 1.0),
+    unit(&["kg/h"], Dimension::MassFlow, 1.0 / 3600.0),
+    unit(&["t/h"], Dimension::MassFlow, 1e3 / 3600.0),
+    unit(&["lb/h"], Dimension::MassFlow, 0.453_592_37 / 3600.0),
+    unit(&["mol/s"], Dimension::MolarFlow, 1.0),
+    unit(&["mol/h"], Dimension::MolarFlow, 1.0 / 3600.0),
+    unit(&["kmol/s"], Dimension::MolarFlow, 1e3),
+    unit(&["kmol/h"], Dimension::MolarFlow, 1e3 / 3600.0),
+    unit(&["J"], Dimension::Energy, 1.0),
+    unit(&["kJ"], Dimension::Energy, 1e3),
+    unit(&["MJ"], Dimension::Energy, 1e6),
+    unit(&["kWh"], Dimension::Energy, 3.6e6),
+    unit(&["W"], Dimension::Power, 1.0),
+    unit(&["kW"], Dimension::Power, 1e3),
+    unit(&["MW"], Dimension::Power, 1e6),
+];
+
+fn find(symbol: &str) -> Result<&'static Unit, ConversionError> {
+    UNITS
+        .iter()
+        .find(|u| u.symbols.contains(&symbol))
+        .ok_or_else(|| ConversionError::UnknownUnit(symbol.to_owned()))
+}
+
+/// gets the dimension of a unit, `None` if the unit is unknown
+pub fn dimension_of(unit: &str) -> Option<Dimension> {
+    find(unit).ok().map(|u| u.dimension)
+}
+
+/// Converts a value from one unit into another, e.g. `convert(145.0, "psi", "bar")`
+pub fn convert(value: f64, from: &str, to: &str) -> Result<f64, ConversionError> {
+    if from == to {
+        return Ok(value);
+    }
+    let (from_unit, to_unit) = (find(from)?, find(to)?);
+    if from_unit.dimension != to_unit.dimension {
+        return Err(ConversionError::DimensionMismatch {
+            from: from.to_owned(),
+            to: to.to_owned(),
+        });
+    }
+    let si = value * from_unit.factor + from_unit.offset;
+    Ok((si - to_unit.offset) / to_unit.factor)
+}
+
+/// Converts a value into the unit of a field given by `#[unit(..)]`, e.g. an input in psi
+/// for a field in bar
+pub fn to_field_unit<T: Contracts>(
+    field: &str,
+    value: f64,
+    from: &str,
+) -> Result<f64, ConversionError> {
+    let unit = T::field_contract(field)
+        .and_then(|c| c.unit)
+        .ok_or_else(|| ConversionError::NoUnit {
+            ty: T::TYPE_NAME,
+            field: field.to_owned(),
+        })?;
+    convert(value, from, unit)
+}
+
+/// converts a temperature from °C to K
+pub fn celsius_to_kelvin(celsius: f64) -> f64 {
+    celsius + 273.15
+}
+
+/// converts a temperature from K to °C
+pub fn kelvin_to_celsius(kelvin: f64) -> f64 {
+    kelvin - 273.15
+}
+
+/// converts a temperature from °F to K
+pub fn fahrenheit_to_kelvin(fahrenheit: f64) -> f64 {
+    (fahrenheit - 32.0) * 5.0 / 9.0 + 273.15
+}
+
+/// converts a pressure from psi to bar
+pub fn psi_to_bar(psi: f64) -> f64 {
+    psi * 6_894.757_293_168 / 1e5
+}
+
+/// converts a pressure from bar to psi
+pub fn bar_to_psi(bar: f64) -> f64 {
+    bar * 1e5 / 6_894.757_293_168
+}
+
+impl Display for ConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConversionError::UnknownUnit(unit) => write!(f, "unknown unit '{}'", unit),
+            ConversionError::DimensionMismatch { from, to } => write!(
+                f,
+                "cannot convert '{}' ({:?}) into '{}' ({:?})",
+                from,
+                find(from).map(|u| u.dimension).ok(),
+                to,
+                find(to).map(|u| u.dimension).ok()
+            ),
+            ConversionError::NoUnit { ty, field } => {
+                write!(f, "the field '{}' of '{}' has no unit", field, ty)
+            }
+        }
+    }
+}
+
+impl Error for ConversionError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::*;
+
+    #[derive(Debug, Clone, PartialEq, engcon_macros::Validatable)]
+    struct Vessel {
+        #[unit("bar")]
+        #[validate_value(x > 0.0, x <= 10.0)]
+        pressure: f64,
+        #[unit("K")]
+        #[validate_value(x >= 273.15)]
+        temperature: f64,
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn values_are_converted_between_units() {
+        assert_close(convert(25.0, "°C", "K").unwrap(), 298.15);
+        assert_close(convert(212.0, "degF", "°C").unwrap(), 100.0);
+        assert_close(convert(1.0, "atm", "kPa").unwrap(), 101.325);
+        assert_close(convert(3.6, "kmol/h", "mol/s").unwrap(), 1.0);
+        assert_close(psi_to_bar(bar_to_psi(12.0)), 12.0);
+        assert_close(fahrenheit_to_kelvin(32.0), celsius_to_kelvin(0.0));
+
+        assert!(matches!(
+            convert(1.0, "bar", "K"),
+            Err(ConversionError::DimensionMismatch { .. })
+        ));
+        assert_eq!(
+            convert(1.0, "furlong", "m"),
+            Err(ConversionError::UnknownUnit("furlong".to_owned()))
+        );
+    }
+
+    #[test]
+    fn inputs_are_converted_into_the_field_unit() {
+        let vessel = Vessel {
+            pressure: to_field_unit::<Vessel>("pressure", 174.0, "psi").unwrap(),
+            temperature: to_field_unit::<Vessel>("temperature", 20.0, "°C").unwrap(),
+        };
+        assert_close(vessel.temperature, 293.15);
+
+        let err = vessel.validate().unwrap_err();
+        assert!(err.msg().starts_with("value=11.99"));
+        assert!(err.msg().ends_with(" bar: 'pressure' <= '10.0 bar'"));
+    }
+}
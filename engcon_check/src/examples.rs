@@ -0,0 +1,19 @@
+//! The types that the `engcon-check` binary registers.
+
+use engcon::*;
+
+/// the distillation column of the examples
+#[derive(Debug, Clone, serde::Deserialize, Validatable)]
+pub struct DistillationColumn {
+    #[validate_value(x >= 3)]
+    pub trays: i32,
+
+    #[validate_value(x < trays, x >= 1)]
+    pub feed_place: i32,
+
+    #[validate_value(x > 0.0)]
+    pub reflux_ratio: f32,
+
+    #[validate_value(x > 0.0, x < 1.0)]
+    pub distiliate_to_feed_ratio: f32,
+}
@@ -0,0 +1,201 @@
+//! Reading the records of CSV, JSON and TOML input files.
+
+use std::{error::Error, fmt::Display, path::Path};
+
+use serde_json::{Map, Number, Value};
+
+/// The format of an input file, given by its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// every row is a record, the header gives the field names
+    Csv,
+    /// an array of records or a single record
+    Json,
+    /// a single record or arrays of tables whose entries are the records
+    Toml,
+}
+
+/// A record of an input file with a label for the output, e.g. `cases.csv:3`
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputRecord {
+    pub label: String,
+    pub value: Value,
+}
+
+/// An error that occurs if an input file cannot be read or parsed
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputError {
+    pub file: String,
+    pub msg: String,
+}
+
+impl Format {
+    /// gets the format by the extension of the file
+    pub fn of(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()? {
+            "csv" => Some(Format::Csv),
+            "json" => Some(Format::Json),
+            "toml" => Some(Format::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// reads all records of an input file
+pub fn read_records(path: &Path) -> Result<Vec<InputRecord>, InputError> {
+    let file = path.display().to_string();
+    let error = |msg: String| InputError {
+        file: file.clone(),
+        msg,
+    };
+    let format = Format::of(path)
+        .ok_or_else(|| error("unsupported format, expected csv, json or toml".to_owned()))?;
+    let src = std::fs::read_to_string(path).map_err(|err| error(err.to_string()))?;
+    parse_records(&file, format, &src)
+}
+
+/// parses the records of the content `src` of the file with the name `file`
+pub fn parse_records(
+    file: &str,
+    format: Format,
+    src: &str,
+) -> Result<Vec<InputRecord>, InputError> {
+    let error = |msg: String| InputError {
+        file: file.to_owned(),
+        msg,
+    };
+    let label = |n: usize| format!("{}:{}", file, n);
+
+    match format {
+        Format::Csv => {
+            let mut reader = csv::ReaderBuilder::new()
+                .trim(csv::Trim::All)
+                .from_reader(src.as_bytes());
+            let headers = reader
+                .headers()
+                .map_err(|err| error(err.to_string()))?
+                .clone();
+            let mut records = vec![];
+            for row in reader.records() {
+                let row = row.map_err(|err| error(err.to_string()))?;
+                let line = row.position().map_or(0, |p| p.line() as usize);
+                let value: Map<String, Value> = headers
+                    .iter()
+                    .zip(row.iter())
+                    .map(|(header, cell)| (header.to_owned(), cell_value(cell)))
+                    .collect();
+                records.push(InputRecord {
+                    label: label(line),
+                    value: Value::Object(value),
+                });
+            }
+            Ok(records)
+        }
+        Format::Json => {
+            let value: Value = serde_json::from_str(src).map_err(|err| error(err.to_string()))?;
+            Ok(match value {
+                Value::Array(values) => values
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, value)| InputRecord {
+                        label: label(i + 1),
+                        value,
+                    })
+                    .collect(),
+                value => vec![InputRecord {
+                    label: file.to_owned(),
+                    value,
+                }],
+            })
+        }
+        Format::Toml => {
+            let value: Value = toml::from_str(src).map_err(|err| error(err.to_string()))?;
+            let tables = match &value {
+                Value::Object(map) if !map.is_empty() && map.values().all(Value::is_array) => {
+                    Some(map)
+                }
+                _ => None,
+            };
+            Ok(match tables {
+                // e.g. `[[column]]` tables, labeled by the name of the array
+                Some(map) => map
+                    .iter()
+                    .flat_map(|(name, values)| {
+                        let values = values.as_array().into_iter().flatten();
+                        values.enumerate().map(move |(i, value)| InputRecord {
+                            label: format!("{}:{}[{}]", file, name, i + 1),
+                            value: value.clone(),
+                        })
+                    })
+                    .collect(),
+                None => vec![InputRecord {
+                    label: file.to_owned(),
+                    value,
+                }],
+            })
+        }
+    }
+}
+
+/// a number, a boolean or a string, empty cells are `null`
+fn cell_value(cell: &str) -> Value {
+    if cell.is_empty() {
+        Value::Null
+    } else if let Ok(int) = cell.parse::<i64>() {
+        Value::Number(int.into())
+    } else if let Some(number) = cell.parse::<f64>().ok().and_then(Number::from_f64) {
+        Value::Number(number)
+    } else if let Ok(boolean) = cell.parse::<bool>() {
+        Value::Bool(boolean)
+    } else {
+        Value::String(cell.to_owned())
+    }
+}
+
+impl Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "cannot read '{}': {}", self.file, self.msg)
+    }
+}
+
+impl Error for InputError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn csv_rows_are_labeled_by_their_line() {
+        let src = "trays, feed_place, name\n10, 5, C-101\n20, 2.5,\n";
+        let records = parse_records("cases.csv", Format::Csv, src).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].label, "cases.csv:2");
+        assert_eq!(
+            records[0].value,
+            json!({"trays": 10, "feed_place": 5, "name": "C-101"})
+        );
+        assert_eq!(
+            records[1].value,
+            json!({"trays": 20, "feed_place": 2.5, "name": null})
+        );
+    }
+
+    #[test]
+    fn json_and_toml_hold_one_or_many_records() {
+        let records = parse_records("a.json", Format::Json, r#"[{"trays": 1}, {}]"#).unwrap();
+        assert_eq!(records[1].label, "a.json:2");
+
+        let records = parse_records("a.json", Format::Json, r#"{"trays": 1}"#).unwrap();
+        assert_eq!(records[0].label, "a.json");
+
+        let src = "[[column]]\ntrays = 10\n[[column]]\ntrays = 2\n";
+        let records = parse_records("a.toml", Format::Toml, src).unwrap();
+        assert_eq!(records[1].label, "a.toml:column[2]");
+        assert_eq!(records[1].value, json!({"trays": 2}));
+
+        let records = parse_records("a.toml", Format::Toml, "trays = 10").unwrap();
+        assert_eq!(records[0].value, json!({"trays": 10}));
+        assert!(parse_records("a.toml", Format::Toml, "trays = ").is_err());
+    }
+}
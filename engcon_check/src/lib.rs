@@ -0,0 +1,365 @@
+//! # engcon-check
+//!
+//! Checks the records of CSV, JSON and TOML files against engineering contracts without
+//! writing Rust. The contracts are given by a rule file, see [engcon::rules], by a type that is
+//! registered in a [Registry] or by both, i.e. a registered type with an overlay of runtime
+//! rules.
+//!
+//! ```text
+//! engcon-check --rules limits.toml columns.csv
+//! engcon-check --type DistillationColumn --format json columns/*.toml
+//! ```
+//!
+//! The exit code is 0 if every record is valid, 1 if a record is invalid and 2 if an input
+//! cannot be read.
+//!
+//! The `engcon-check` binary registers the distillation column of the [examples]. Use [run] with
+//! your own [Registry] to check your own types:
+//!
+//! ```no_run
+//! use clap::Parser;
+//! use engcon::*;
+//! use engcon_check::{run, Args, Registry};
+//!
+//! #[derive(Debug, serde::Deserialize, Validatable)]
+//! struct Pump {
+//!     #[validate_value(x > 0.0)]
+//!     head: f64,
+//! }
+//!
+//! fn main() -> std::process::ExitCode {
+//!     let mut registry = Registry::new();
+//!     registry.register::<Pump>();
+//!     run(&registry, Args::parse())
+//! }
+//! ```
+
+use std::{io::Write, path::PathBuf, process::ExitCode};
+
+use engcon::{
+    rules::{RuleError, RuleSet, TypedRuleSet},
+    Contracts, ValidationError, ValidationReport, Validator,
+};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+pub mod examples;
+
+pub mod input;
+use input::{read_records, InputRecord};
+
+/// The arguments of the command line
+#[derive(Debug, Clone, clap::Parser)]
+#[command(
+    name = "engcon-check",
+    version,
+    about = "Validates the records of CSV, JSON and TOML files against engineering contracts"
+)]
+pub struct Args {
+    /// a rule file in the TOML, JSON or YAML format
+    #[arg(short, long)]
+    pub rules: Option<PathBuf>,
+
+    /// the name of a registered type, see --list-types
+    #[arg(short = 't', long = "type")]
+    pub ty: Option<String>,
+
+    /// the output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Table)]
+    pub format: OutputFormat,
+
+    /// prints the names of the registered types
+    #[arg(long)]
+    pub list_types: bool,
+
+    /// the input files, the format is given by the extension csv, json or toml
+    #[arg(required_unless_present = "list_types")]
+    pub inputs: Vec<PathBuf>,
+}
+
+/// The format of the results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// a pass/fail table with the ids of the violated rules
+    Table,
+    /// a JSON array with the validation report of every record
+    Json,
+}
+
+/// A type whose contracts can be checked by name
+#[derive(Debug, Clone, Copy)]
+pub struct RegisteredType {
+    pub name: &'static str,
+    validate: fn(&Value) -> ValidationReport,
+    for_type: fn(RuleSet) -> Result<RuleSet, RuleError>,
+}
+
+/// The types that can be checked by `--type`
+#[derive(Debug, Clone, Default)]
+pub struct Registry {
+    types: Vec<RegisteredType>,
+}
+
+impl Registry {
+    /// generates an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// registers `T` by its [type name](Contracts::TYPE_NAME)
+    pub fn register<T: Contracts + Validator + DeserializeOwned>(&mut self) -> &mut Self {
+        self.types.push(RegisteredType {
+            name: T::TYPE_NAME,
+            validate: validate_value::<T>,
+            for_type: |rules| rules.for_type::<T>().map(TypedRuleSet::into_inner),
+        });
+        self
+    }
+
+    /// gets the registered type with the given name
+    pub fn get(&self, name: &str) -> Option<&RegisteredType> {
+        self.types.iter().find(|t| t.name == name)
+    }
+
+    /// the registered types in the order of registration
+    pub fn types(&self) -> &[RegisteredType] {
+        &self.types
+    }
+}
+
+/// deserializes the record into `T` and checks every rule
+fn validate_value<T: Validator + DeserializeOwned + Contracts>(value: &Value) -> ValidationReport {
+    match T::deserialize(value) {
+        Ok(instance) => instance.validate_all(),
+        Err(err) => {
+            let mut report = ValidationReport::new(T::TYPE_NAME.to_owned());
+            report.push(ValidationError::new(
+                format!("cannot read the record: {}", err),
+                T::TYPE_NAME.to_owned(),
+            ));
+            report
+        }
+    }
+}
+
+/// Checks records against a registered type, runtime rules or both
+#[derive(Debug, Clone)]
+pub struct Checker<'a> {
+    ty: Option<&'a RegisteredType>,
+    rules: Option<RuleSet>,
+}
+
+impl<'a> Checker<'a> {
+    /// Generates a checker, the rules are an overlay that is checked against the fields of the
+    /// type if both are given
+    pub fn new(ty: Option<&'a RegisteredType>, rules: Option<RuleSet>) -> Result<Self, RuleError> {
+        let rules = match (ty, rules) {
+            (Some(ty), Some(rules)) => Some((ty.for_type)(rules)?),
+            (_, rules) => rules,
+        };
+        Ok(Checker { ty, rules })
+    }
+
+    /// checks every contract on the record, the compiled contracts first
+    pub fn check(&self, record: &Value) -> ValidationReport {
+        let mut report = match self.ty {
+            Some(ty) => (ty.validate)(record),
+            None => ValidationReport::new(
+                self.rules
+                    .as_ref()
+                    .and_then(RuleSet::name)
+                    .unwrap_or("record")
+                    .to_owned(),
+            ),
+        };
+        if let Some(rules) = &self.rules {
+            for err in rules.validate_record_all(record).errors() {
+                report.push(err.clone());
+            }
+        }
+        report
+    }
+}
+
+/// Runs the command line with the given registry and returns the exit code
+pub fn run(registry: &Registry, args: Args) -> ExitCode {
+    if args.list_types {
+        for ty in registry.types() {
+            println!("{}", ty.name);
+        }
+        return ExitCode::SUCCESS;
+    }
+
+    match check_inputs(registry, &args) {
+        Ok(results) => {
+            let valid = results.iter().all(|(_, report)| report.is_valid());
+            let mut out = std::io::stdout().lock();
+            let written = match args.format {
+                OutputFormat::Table => write_table(&mut out, &results),
+                OutputFormat::Json => write_json(&mut out, &results),
+            };
+            if let Err(err) = written {
+                eprintln!("error: {}", err);
+                return ExitCode::from(2);
+            }
+            if valid {
+                ExitCode::SUCCESS
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// reads every input and checks its records
+fn check_inputs(
+    registry: &Registry,
+    args: &Args,
+) -> Result<Vec<(InputRecord, ValidationReport)>, Box<dyn std::error::Error>> {
+    let ty = match &args.ty {
+        Some(name) => Some(
+            registry
+                .get(name)
+                .ok_or_else(|| format!("unknown type '{}', see --list-types", name))?,
+        ),
+        None => None,
+    };
+    let rules = args.rules.as_ref().map(RuleSet::load).transpose()?;
+    if ty.is_none() && rules.is_none() {
+        return Err("either --rules or --type is needed".into());
+    }
+    let checker = Checker::new(ty, rules)?;
+
+    let mut results = vec![];
+    for path in &args.inputs {
+        for record in read_records(path)? {
+            let report = checker.check(&record.value);
+            results.push((record, report));
+        }
+    }
+    Ok(results)
+}
+
+/// writes a pass/fail table with the ids of the violated rules or the messages of other errors
+pub fn write_table<W: Write>(
+    mut out: W,
+    results: &[(InputRecord, ValidationReport)],
+) -> std::io::Result<()> {
+    let width = results
+        .iter()
+        .map(|(record, _)| record.label.len())
+        .chain(["RECORD".len()])
+        .max()
+        .unwrap_or_default();
+
+    writeln!(out, "{:width$}  RESULT  RULES", "RECORD")?;
+    for (record, report) in results {
+        let result = if report.is_valid() { "pass" } else { "FAIL" };
+        let rules: Vec<_> = report
+            .errors()
+            .iter()
+            .map(|err| err.rule().unwrap_or(err.msg()))
+            .collect();
+        let line = format!(
+            "{:width$}  {:6}  {}",
+            record.label,
+            result,
+            rules.join(", ")
+        );
+        writeln!(out, "{}", line.trim_end())?;
+    }
+
+    let valid = results.iter().filter(|(_, r)| r.is_valid()).count();
+    writeln!(out, "\n{} of {} records are valid", valid, results.len())
+}
+
+/// writes a JSON array with the label and the validation report of every record
+pub fn write_json<W: Write>(
+    out: W,
+    results: &[(InputRecord, ValidationReport)],
+) -> std::io::Result<()> {
+    #[derive(serde::Serialize)]
+    struct Line<'a> {
+        record: &'a str,
+        #[serde(flatten)]
+        report: &'a ValidationReport,
+    }
+
+    let lines: Vec<_> = results
+        .iter()
+        .map(|(record, report)| Line {
+            record: &record.label,
+            report,
+        })
+        .collect();
+    serde_json::to_writer_pretty(out, &lines)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use examples::DistillationColumn;
+    use input::{parse_records, Format};
+
+    fn results(checker: &Checker) -> Vec<(InputRecord, ValidationReport)> {
+        let src = "\
+trays,feed_place,reflux_ratio,distiliate_to_feed_ratio
+10,5,2.0,0.5
+50,60,2.0,0.5
+10,,2.0,0.5
+";
+        parse_records("cases.csv", Format::Csv, src)
+            .unwrap()
+            .into_iter()
+            .map(|record| {
+                let report = checker.check(&record.value);
+                (record, report)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn registered_types_are_checked_with_an_overlay() {
+        let mut registry = Registry::new();
+        registry.register::<DistillationColumn>();
+        let ty = registry.get("DistillationColumn");
+
+        let typo = RuleSet::from_toml("[rules]\ntray = \"x <= 40\"").unwrap();
+        assert!(Checker::new(ty, Some(typo)).is_err());
+
+        let overlay = RuleSet::from_toml("[rules]\ntrays = \"x <= 40\"").unwrap();
+        let checker = Checker::new(ty, Some(overlay)).unwrap();
+        let results = results(&checker);
+        assert!(results[0].1.is_valid());
+        let rules: Vec<_> = results[1].1.errors().iter().map(|e| e.rule()).collect();
+        assert_eq!(rules, [Some("feed_place < trays"), Some("trays <= 40")]);
+        assert!(results[2].1.errors()[0]
+            .msg()
+            .starts_with("cannot read the record"));
+    }
+
+    #[test]
+    fn results_are_written_as_table_or_json() {
+        let rules = RuleSet::from_toml("[rules]\nfeed_place = \"x < trays\"").unwrap();
+        let checker = Checker::new(None, Some(rules)).unwrap();
+        let results = results(&checker);
+
+        let mut table = vec![];
+        write_table(&mut table, &results).unwrap();
+        let table = String::from_utf8(table).unwrap();
+        assert!(table.contains("cases.csv:3  FAIL    feed_place < trays\n"));
+        assert!(table.ends_with("1 of 3 records are valid\n"));
+
+        let mut json = vec![];
+        write_json(&mut json, &results).unwrap();
+        let json: Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(json[1]["record"], "cases.csv:3");
+        assert_eq!(json[1]["valid"], false);
+        assert_eq!(json[1]["errors"][0]["rule"], "feed_place < trays");
+    }
+}
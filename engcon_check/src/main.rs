@@ -0,0 +1,10 @@
+use std::process::ExitCode;
+
+use clap::Parser;
+use engcon_check::{examples::DistillationColumn, run, Args, Registry};
+
+fn main() -> ExitCode {
+    let mut registry = Registry::new();
+    registry.register::<DistillationColumn>();
+    run(&registry, Args::parse())
+}
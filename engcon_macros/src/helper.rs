@@ -1,4 +1,6 @@
-use proc_macro2::Span;
+use proc_macro2::TokenStream;
+use proc_macro2::TokenTree;
+use quote::ToTokens;
 use syn::punctuated::Punctuated;
 use syn::Expr;
 use syn::Path;
@@ -20,8 +22,9 @@ pub(crate) fn adapt_ident_with_self(expr: &mut Expr) -> bool {
             // check if we're using just an ident:
             if orig_path.path.segments.len() == 1 {
                 let ident = orig_path.path.segments.first().unwrap().ident.clone();
+                // keep the span of the field, so errors point at the contract
                 let self_segment = PathSegment {
-                    ident: syn::Ident::new("self", Span::call_site()),
+                    ident: syn::Ident::new("self", ident.span()),
                     arguments: syn::PathArguments::None,
                 };
                 let mut segments = Punctuated::new();
@@ -54,3 +57,87 @@ pub(crate) fn adapt_ident_with_self(expr: &mut Expr) -> bool {
         false
     }
 }
+
+/// Checks if the type is a primitive integer type like `i32` or `usize`
+pub(crate) fn is_integer_type(ty: &syn::Type) -> bool {
+    matches!(
+        primitive_name(ty).as_deref(),
+        Some(
+            "i8" | "i16"
+                | "i32"
+                | "i64"
+                | "i128"
+                | "isize"
+                | "u8"
+                | "u16"
+                | "u32"
+                | "u64"
+                | "u128"
+                | "usize"
+        )
+    )
+}
+
+/// Checks if the type is a primitive float type, i.e. `f32` or `f64`
+pub(crate) fn is_float_type(ty: &syn::Type) -> bool {
+    matches!(primitive_name(ty).as_deref(), Some("f32" | "f64"))
+}
+
+/// true for the primitive number types, see [is_integer_type] and [is_float_type]
+pub(crate) fn is_primitive_type(ty: &syn::Type) -> bool {
+    is_integer_type(ty) || is_float_type(ty)
+}
+
+fn primitive_name(ty: &syn::Type) -> Option<String> {
+    match ty {
+        syn::Type::Path(p) if p.qself.is_none() => p.path.get_ident().map(|i| i.to_string()),
+        _ => None,
+    }
+}
+
+/// Checks if an expression contains the given identifier anywhere, e.g. `ctx` in `ctx.max_pressure`
+pub(crate) fn expr_mentions_ident(expr: &Expr, ident: &str) -> bool {
+    fn walk(tokens: TokenStream, ident: &str) -> bool {
+        tokens.into_iter().any(|tt| match tt {
+            TokenTree::Ident(i) => i == ident,
+            TokenTree::Group(g) => walk(g.stream(), ident),
+            _ => false,
+        })
+    }
+    walk(expr.to_token_stream(), ident)
+}
+
+/// Gets the numeric value of a literal expression like `3`, `0.5` or `-1.0`
+pub(crate) fn literal_value(expr: &Expr) -> Option<f64> {
+    match expr {
+        Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(i),
+            ..
+        }) => i.base10_parse::<f64>().ok(),
+        Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Float(f),
+            ..
+        }) => f.base10_parse::<f64>().ok(),
+        Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => literal_value(expr).map(|v| -v),
+        Expr::Paren(p) => literal_value(&p.expr),
+        _ => None,
+    }
+}
+
+/// Prints tokens in a readable way, e.g. `ctx.max_pressure` instead of `ctx . max_pressure`
+pub(crate) fn tokens_to_string<T: ToTokens>(tokens: &T) -> String {
+    let s = tokens
+        .to_token_stream()
+        .to_string()
+        .replace(" . ", ".")
+        .replace(" :: ", "::");
+    // negative literals like `- 1.0`
+    match s.strip_prefix("- ") {
+        Some(rest) => format!("-{}", rest),
+        None => s,
+    }
+}
@@ -16,6 +16,9 @@ mod validator;
 
 /// Makes a type validateable, means it implements the [engcon::Validator] trait.
 ///
+/// The rules are also available at runtime by the [engcon::Contracts] trait, e.g. to show
+/// the allowed range of a field in a GUI.
+///
 /// Core idea is to get the domain contracts from experts, i.e. engineers or
 /// other staff, in an easy readable approach for easy interdisciplinary discussions.
 ///
@@ -38,10 +41,187 @@ mod validator;
 /// }
 /// ```
 ///
+/// # Example - Context-dependent Contracts
+///
+/// Contracts that depend on data outside of the struct refer to it as `ctx`. The type of
+/// the context is given by `#[validate(context = Type)]`. Instead of [engcon::Validator]
+/// the type then implements [engcon::ValidatorWithContext].
+///
+/// ```
+/// use engcon::*;
+/// use engcon_macros::Validatable;
+/// pub struct PlantLimits {
+///     pub max_pressure: f64,
+/// }
+///
+/// #[derive(Debug, Clone, PartialEq, Validatable)]
+/// #[validate(context = PlantLimits)]
+/// pub struct Vessel {
+///     #[validate_value(x > 0.0, x <= ctx.max_pressure)]
+///     pub design_pressure: f64,
+/// }
+///
+/// let limits = PlantLimits { max_pressure: 10.0 };
+/// let vessel = Validated::try_new_with(Vessel { design_pressure: 8.0 }, &limits);
+/// assert!(vessel.is_ok());
+/// ```
+///
+/// # Nested Validation
+///
+/// A field whose type implements [engcon::Validator] itself, e.g. the newtypes in
+/// `engcon::types`, is validated together with the struct if it is marked with
+/// `#[validate(nested)]`. The errors give the path of the violated rule, e.g. `quality.0`.
+///
+/// ```
+/// use engcon::*;
+/// use engcon::types::Fraction;
+/// use engcon_macros::Validatable;
+///
+/// #[derive(Debug, Clone, Validatable)]
+/// pub struct Feed {
+///     #[validate_value(x > 0.0)]
+///     pub flow: f64,
+///     #[validate(nested)]
+///     pub quality: Fraction,
+/// }
+///
+/// let err = Feed { flow: 1.0, quality: Fraction(1.5) }.validate().unwrap_err();
+/// assert_eq!(err.rule(), Some("quality.0 <= 1.0"));
+/// ```
+///
+/// # Physical Units
+///
+/// Fields can be quantities of the [uom](https://docs.rs/uom) crate, re-exported by the
+/// `uom` feature of engcon. A number in a rule is then followed by its unit, which must be in
+/// scope. Rules are checked dimensionally, e.g. a unit of temperature on a pressure or the
+/// comparison of a pressure with a temperature field is a compile error at the rule naming
+/// both types, `cannot compare 'temperature: ThermodynamicTemperature' with 'design_pressure:
+/// Pressure', the types differ`.
+///
+/// ```
+/// use engcon::*;
+/// use engcon::uom::si::f64::Pressure;
+/// use engcon::uom::si::pressure::bar;
+/// use engcon_macros::Validatable;
+///
+/// #[derive(Debug, Clone, Validatable)]
+/// pub struct Vessel {
+///     #[validate_value(x > 1.0 bar, x <= max_pressure)]
+///     pub design_pressure: Pressure,
+///     pub max_pressure: Pressure,
+/// }
+///
+/// let vessel = Vessel {
+///     design_pressure: Pressure::new::<bar>(8.0),
+///     max_pressure: Pressure::new::<bar>(10.0),
+/// };
+/// assert!(vessel.validate().is_ok());
+/// ```
+///
+/// ```compile_fail
+/// use engcon::*;
+/// use engcon::uom::si::f64::{Pressure, ThermodynamicTemperature};
+/// use engcon_macros::Validatable;
+///
+/// #[derive(Debug, Clone, Validatable)]
+/// pub struct Vessel {
+///     pub design_pressure: Pressure,
+///     #[validate_value(x < design_pressure)]
+///     pub temperature: ThermodynamicTemperature,
+/// }
+/// ```
+///
+/// Teams that don't use a units crate can annotate a field with its unit instead. The unit
+/// is stored in [engcon::FieldContract::unit] and shown in error messages and datasheets.
+/// Only fields with rules have a contract, `#[unit]` without `#[validate_value]` is an error:
+///
+/// ```
+/// use engcon::*;
+/// use engcon_macros::Validatable;
+///
+/// #[derive(Debug, Clone, Validatable)]
+/// pub struct Vessel {
+///     #[unit("bar")]
+///     #[validate_value(x <= 10)]
+///     pub pressure: f64,
+/// }
+///
+/// let err = Vessel { pressure: 12.0 }.validate().unwrap_err();
+/// assert_eq!(err.msg(), "value=12 bar: 'pressure' <= '10.0 bar'");
+/// ```
+///
+/// # Type Checks
+///
+/// The operands of a rule are checked against the type of the field. An integer literal
+/// on a float field is coerced, i.e. `x > 0` on a `f32` means `x > 0.0`. A float literal on
+/// an integer field or a sibling field of another type gives an error at the operand:
+///
+/// ```compile_fail
+/// use engcon::*;
+/// use engcon_macros::Validatable;
+/// #[derive(Debug, Clone, Validatable)]
+/// pub struct Mismatch {
+///     pub trays: i32,
+///     #[validate_value(x > 0, x < trays)]
+///     pub reflux_ratio: f32,
+/// }
+/// ```
+///
+/// # Contradictory and Redundant Rules
+///
+/// Constant bounds of a field are checked at compile time. If no value can satisfy the
+/// rules, the derive fails:
+///
+/// ```compile_fail
+/// use engcon::*;
+/// use engcon_macros::Validatable;
+/// #[derive(Debug, Clone, Validatable)]
+/// pub struct Broken {
+///     #[validate_value(x > 5, x < 3)]
+///     pub trays: i32,
+/// }
+/// ```
+///
+/// Cross-field rules must refer to existing fields and must not form a cycle of strict
+/// inequalities, like `a < b` together with `b < a`:
+///
+/// ```compile_fail
+/// use engcon::*;
+/// use engcon_macros::Validatable;
+/// #[derive(Debug, Clone, Validatable)]
+/// pub struct Cyclic {
+///     #[validate_value(x < b)]
+///     pub a: i32,
+///     #[validate_value(x < a)]
+///     pub b: i32,
+/// }
+/// ```
+///
+/// Rules that are implied by a tighter rule, e.g. `x > 0` in `x > 0, x > 1`, generate a
+/// deprecation warning that points at the redundant rule. The warning is named after the rule,
+/// e.g. `redundant_rule_x_gt_0`, and explains which rule implies it.
+///
 /// # Generated Code
 ///
+/// The generated code refers to the items of engcon by their full path, e.g.
+/// `::engcon::ValidationError`, so they don't need to be imported.
+///
+/// - A free function and a method `contract_<field>` for every validated field.
+/// - An implementation of [engcon::Validator], or [engcon::ValidatorWithContext] if a context
+///   is given, and of `TryFrom<T>` for `Validated<T>` without a context.
+/// - An implementation of [engcon::Contracts] describing the rules and the names of all fields.
+/// - A method `validate_with_stats()` that checks every rule and counts its passes and failures
+///   in an [engcon::ValidationStats], e.g. to find the rules that reject the most cases.
+/// - If all validated fields are primitive numbers, the methods `violations()`, `violation()`
+///   and `penalty()` that measure how badly the rules are violated, e.g. for optimizers, and
+///   `margins()` and `active_rules()` that give the slack of the rules, e.g. for design reviews,
+///   and `repair()` that clamps the fields into their valid range. Such types also implement
+///   `engcon::Sample`, which draws the fields in dependency order, e.g. for Monte-Carlo studies,
+///   and `engcon::BoundaryCases`, which generates test cases at the bound of every rule. Both
+///   are implemented independent of the features of engcon, only `engcon::UniformSampler`
+///   needs the `rand` feature.
 ///
-#[proc_macro_derive(Validatable, attributes(validate_value))]
+#[proc_macro_derive(Validatable, attributes(validate_value, validate, unit))]
 pub fn validate(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     // Parse the input tokens into a syntax tree
     let input = parse_macro_input!(input as DeriveInput);
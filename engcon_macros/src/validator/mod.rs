@@ -1,5 +1,5 @@
-use syn::{parse::Parse, DeriveInput, Type};
-use validator_analyze::analyze_ast;
+use syn::{parse::Parse, spanned::Spanned, DeriveInput, Type};
+use validator_analyze::{analyze_ast, analyze_bounds, analyze_dependencies, analyze_types};
 use validator_codegen::codegen;
 use validator_filter::filter_ast;
 use validator_intermediate::intermediate_code;
@@ -8,6 +8,7 @@ use crate::helper::*;
 
 mod validator_analyze;
 mod validator_codegen;
+mod validator_codegen_numeric;
 mod validator_filter;
 mod validator_intermediate;
 
@@ -15,6 +16,9 @@ pub(super) fn validator_macro_wrapper(input: DeriveInput) -> MacroResult {
     let ast = filter_ast(input).map_err(synerr_to_tokens)?;
     let ast = analyze_ast(ast).map_err(synerr_to_tokens)?;
     let ic = intermediate_code(ast).map_err(synerr_to_tokens)?;
+    let ic = analyze_dependencies(ic).map_err(synerr_to_tokens)?;
+    let ic = analyze_types(ic).map_err(synerr_to_tokens)?;
+    let ic = analyze_bounds(ic).map_err(synerr_to_tokens)?;
 
     Ok(codegen(ic))
 }
@@ -23,7 +27,14 @@ pub(super) fn validator_macro_wrapper(input: DeriveInput) -> MacroResult {
 struct PartialAST {
     self_type: syn::Ident,
 
+    context: Option<Type>,
+
+    all_fields: Vec<syn::Field>,
+
     validated_fields: Vec<syn::Field>,
+
+    /// fields marked with `#[validate(nested)]` whose type implements `Validator`
+    nested_fields: Vec<syn::Ident>,
 }
 
 #[derive(Debug)]
@@ -35,6 +46,15 @@ struct ValidationRule {
     rigth: syn::Expr,
 
     right_is_field_on_self: bool,
+
+    right_uses_context: bool,
+
+    /// the unit of a literal like `1.0 bar` for quantity types like `uom::si::f64::Pressure`
+    unit: Option<syn::Path>,
+
+    /// the type of a sibling that is no primitive number, e.g. `Pressure` in `x < max_pressure`,
+    /// the generated code checks that it equals the type of the field
+    sibling_ty: Option<Type>,
 }
 
 #[derive(Debug)]
@@ -44,13 +64,84 @@ struct FieldInfo {
     ty: Type,
 
     rules: Vec<ValidationRule>,
+
+    /// the unit given by `#[unit("bar")]`
+    unit: Option<String>,
 }
 
 #[derive(Debug)]
 struct IntermediateCode {
     self_type: syn::Ident,
 
+    context: Option<Type>,
+
+    all_fields: Vec<syn::Field>,
+
     field_infos: Vec<FieldInfo>,
+
+    nested_fields: Vec<syn::Ident>,
+
+    lints: Vec<Lint>,
+}
+
+/// A warning that is emitted at the given span, e.g. for redundant rules
+#[derive(Debug)]
+struct Lint {
+    span: proc_macro2::Span,
+
+    /// the name of the warning, e.g. `redundant_rule_x_gt_0`
+    name: String,
+
+    msg: String,
+}
+
+impl ValidationRule {
+    /// gets the sibling field for rules like `x < trays`
+    fn sibling(&self) -> Option<&syn::Ident> {
+        match (&self.rigth, self.right_is_field_on_self) {
+            (
+                syn::Expr::Field(syn::ExprField {
+                    member: syn::Member::Named(ident),
+                    ..
+                }),
+                true,
+            ) => Some(ident),
+            _ => None,
+        }
+    }
+
+    /// true for `<` and `<=`, i.e. the right hand side is an upper bound
+    fn is_upper_bound(&self) -> bool {
+        matches!(self.cmp_op, syn::BinOp::Lt(_) | syn::BinOp::Le(_))
+    }
+
+    /// true for `<` and `>`
+    fn is_strict(&self) -> bool {
+        matches!(self.cmp_op, syn::BinOp::Lt(_) | syn::BinOp::Gt(_))
+    }
+
+    /// gets the right operand for a field of the given type, literals with a unit become a
+    /// quantity, e.g. `<Pressure>::new::<bar>(1.0)`
+    fn right_operand(&self, ty: &Type) -> proc_macro2::TokenStream {
+        let right = &self.rigth;
+        match &self.unit {
+            Some(unit) => quote::quote_spanned! {unit.span()=> <#ty>::new::<#unit>(#right)},
+            None => quote::quote! {#right},
+        }
+    }
+
+    /// gets the right hand side as written in the contract, e.g. `1.0 bar`
+    fn rhs_to_string(&self) -> String {
+        match (self.sibling(), &self.unit) {
+            (Some(sibling), _) => sibling.to_string(),
+            (None, Some(unit)) => format!(
+                "{} {}",
+                tokens_to_string(&self.rigth),
+                tokens_to_string(unit)
+            ),
+            (None, None) => tokens_to_string(&self.rigth),
+        }
+    }
 }
 
 impl Parse for ValidationRule {
@@ -60,8 +151,23 @@ impl Parse for ValidationRule {
             cmp_op: input.parse()?,
             rigth: input.parse()?,
             right_is_field_on_self: false,
+            right_uses_context: false,
+            unit: None,
+            sibling_ty: None,
         };
 
+        // a literal may be followed by a unit, e.g. `x > 1.0 bar`
+        if input.peek(syn::Ident) || input.peek(syn::Token![::]) {
+            let unit: syn::Path = input.parse()?;
+            if literal_value(&candidate.rigth).is_none() {
+                return Err(syn::Error::new_spanned(
+                    unit,
+                    "a unit can only follow a number, e.g. `x > 1.0 bar`",
+                ));
+            }
+            candidate.unit = Some(unit);
+        }
+
         // todo: be more strict about phases
         if candidate.left != "x" {
             // a rule starts with an `x` representing the field below
@@ -88,6 +194,9 @@ impl Parse for ValidationRule {
             candidate.right_is_field_on_self = true;
         }
 
+        // a contract like `x <= ctx.max_pressure` depends on external data
+        candidate.right_uses_context = expr_mentions_ident(&candidate.rigth, "ctx");
+
         Ok(candidate)
     }
 }
@@ -1,4 +1,7 @@
-use super::PartialAST;
+use quote::ToTokens;
+
+use super::{FieldInfo, IntermediateCode, Lint, PartialAST, ValidationRule};
+use crate::helper::*;
 
 pub(super) fn analyze_ast(ast: PartialAST) -> Result<PartialAST, syn::Error> {
     // check if validated type is good:
@@ -38,3 +41,348 @@ pub(super) fn analyze_ast(ast: PartialAST) -> Result<PartialAST, syn::Error> {
         Err(err)
     }
 }
+
+/// A constant bound of a rule like `x > 0.0`, integer bounds are normalized to non-strict ones
+struct LiteralBound<'a> {
+    rule: &'a ValidationRule,
+
+    value: f64,
+
+    strict: bool,
+
+    upper: bool,
+}
+
+/// Applies interval arithmetic on the literal bounds of each field.
+///
+/// Rejects fields whose feasible set is empty, e.g. `x > 5, x < 3`, and warns about
+/// redundant rules, e.g. `x > 0` in `x > 0, x > 1`.
+pub(super) fn analyze_bounds(mut ic: IntermediateCode) -> Result<IntermediateCode, syn::Error> {
+    let mut error: Option<syn::Error> = None;
+    let mut lints = vec![];
+
+    for field in &ic.field_infos {
+        let bounds = literal_bounds(field);
+        let (lower, upper): (Vec<_>, Vec<_>) = bounds.iter().partition(|b| !b.upper);
+
+        let tightest_lower = lower.iter().copied().reduce(|a, b| tighter(a, b));
+        let tightest_upper = upper.iter().copied().reduce(|a, b| tighter(a, b));
+        if let (Some(lo), Some(up)) = (tightest_lower, tightest_upper) {
+            let empty = lo.value > up.value || (lo.value == up.value && (lo.strict || up.strict));
+            if empty {
+                let err = syn::Error::new_spanned(
+                    &up.rule.rigth,
+                    format!(
+                        "contradictory rules on '{}': no value satisfies both `{}` and `{}`",
+                        field.field_name,
+                        rule_to_string(lo.rule),
+                        rule_to_string(up.rule),
+                    ),
+                );
+                match &mut error {
+                    Some(e) => e.combine(err),
+                    None => error = Some(err),
+                }
+                continue;
+            }
+        }
+
+        // every rule that is not the tightest on its side is redundant
+        for (side, tightest) in [(&lower, tightest_lower), (&upper, tightest_upper)] {
+            let Some(tightest) = tightest else { continue };
+            for bound in side.iter().filter(|b| !std::ptr::eq(**b, tightest)) {
+                lints.push(Lint {
+                    span: syn::spanned::Spanned::span(&bound.rule.rigth),
+                    name: format!("redundant_rule_{}", rule_to_ident(bound.rule)),
+                    msg: format!(
+                        "`{}` on '{}' is implied by `{}` and can be removed",
+                        rule_to_string(bound.rule),
+                        field.field_name,
+                        rule_to_string(tightest.rule),
+                    ),
+                });
+            }
+        }
+    }
+
+    match error {
+        Some(err) => Err(err),
+        None => {
+            ic.lints.extend(lints);
+            Ok(ic)
+        }
+    }
+}
+
+fn literal_bounds(field: &FieldInfo) -> Vec<LiteralBound<'_>> {
+    let integer = is_integer_type(&field.ty);
+    field
+        .rules
+        .iter()
+        .filter(|rule| rule.unit.is_none())
+        .filter_map(|rule| {
+            let value = literal_value(&rule.rigth)?;
+            let (upper, strict) = (rule.is_upper_bound(), rule.is_strict());
+            // for integers `x > 2` equals `x >= 3`
+            let (value, strict) = match (integer, strict, upper) {
+                (true, true, true) => (value.ceil() - 1.0, false),
+                (true, true, false) => (value.floor() + 1.0, false),
+                (true, false, true) => (value.floor(), false),
+                (true, false, false) => (value.ceil(), false),
+                (false, _, _) => (value, strict),
+            };
+            Some(LiteralBound {
+                rule,
+                value,
+                strict,
+                upper,
+            })
+        })
+        .collect()
+}
+
+/// gets the tighter of two bounds on the same side, the first one if both are equal
+fn tighter<'a, 'b>(a: &'b LiteralBound<'a>, b: &'b LiteralBound<'a>) -> &'b LiteralBound<'a> {
+    let b_is_tighter = if a.value == b.value {
+        b.strict && !a.strict
+    } else {
+        (b.value < a.value) == a.upper
+    };
+    if b_is_tighter {
+        b
+    } else {
+        a
+    }
+}
+
+fn rule_to_string(rule: &ValidationRule) -> String {
+    format!(
+        "{} {} {}",
+        rule.left,
+        rule.cmp_op.to_token_stream(),
+        tokens_to_string(&rule.rigth)
+    )
+}
+
+/// a name of the rule that is an identifier, e.g. `x_gt_0` for `x > 0`
+fn rule_to_ident(rule: &ValidationRule) -> String {
+    let op = match rule.cmp_op {
+        syn::BinOp::Lt(_) => "lt",
+        syn::BinOp::Le(_) => "le",
+        syn::BinOp::Gt(_) => "gt",
+        _ => "ge",
+    };
+    let rhs = tokens_to_string(&rule.rigth).replace('-', "neg_");
+    let rhs: String = rhs
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{}_{}_{}", rule.left, op, rhs)
+}
+
+/// Checks the cross-field rules like `feed_place: x < trays`.
+///
+/// Rejects references to fields that do not exist and cycles of strict inequalities, e.g.
+/// `a: x < b` together with `b: x < a`.
+pub(super) fn analyze_dependencies(ic: IntermediateCode) -> Result<IntermediateCode, syn::Error> {
+    let mut error: Option<syn::Error> = None;
+    let mut push_error = |err: syn::Error| match &mut error {
+        Some(e) => e.combine(err),
+        None => error = Some(err),
+    };
+
+    // edges of the "is below" relation, `a: x < b` gives a -> b and `a: x > b` gives b -> a
+    let mut edges: Vec<(String, String, &ValidationRule)> = vec![];
+    for field in &ic.field_infos {
+        for rule in &field.rules {
+            let Some(sibling) = rule.sibling() else {
+                continue;
+            };
+            if !ic
+                .all_fields
+                .iter()
+                .any(|f| f.ident.as_ref() == Some(sibling))
+            {
+                push_error(syn::Error::new_spanned(
+                    &rule.rigth,
+                    format!("no field '{}' on '{}'", sibling, ic.self_type),
+                ));
+                continue;
+            }
+
+            let (field, sibling) = (field.field_name.to_string(), sibling.to_string());
+            if rule.is_upper_bound() {
+                edges.push((field, sibling, rule));
+            } else {
+                edges.push((sibling, field, rule));
+            }
+        }
+    }
+
+    // a strict edge u -> v on a cycle, i.e. v reaches u, can never hold
+    let mut reported: Vec<String> = vec![];
+    for (from, to, rule) in edges.iter().filter(|e| e.2.is_strict()) {
+        if reported.contains(from) && reported.contains(to) {
+            continue;
+        }
+        if let Some(path) = find_path(&edges, to, from) {
+            let mut cycle = format!("{} < {}", from, to);
+            for pair in path.windows(2) {
+                let strict = edges
+                    .iter()
+                    .any(|e| e.0 == pair[0] && e.1 == pair[1] && e.2.is_strict());
+                cycle.push_str(if strict { " < " } else { " <= " });
+                cycle.push_str(&pair[1]);
+            }
+            push_error(syn::Error::new_spanned(
+                &rule.rigth,
+                format!("unsatisfiable cyclic rules: {} can never hold", cycle),
+            ));
+            reported.push(from.clone());
+            reported.extend(path);
+        }
+    }
+
+    match error {
+        Some(err) => Err(err),
+        None => Ok(ic),
+    }
+}
+
+/// searches a path from `start` to `goal` and returns the visited fields including both ends
+fn find_path(
+    edges: &[(String, String, &ValidationRule)],
+    start: &str,
+    goal: &str,
+) -> Option<Vec<String>> {
+    let mut stack = vec![vec![start.to_owned()]];
+    let mut visited = vec![start.to_owned()];
+    while let Some(path) = stack.pop() {
+        let last = path.last().expect("paths are never empty");
+        if last == goal {
+            return Some(path);
+        }
+        for (_, to, _) in edges.iter().filter(|e| &e.0 == last) {
+            if !visited.contains(to) {
+                visited.push(to.clone());
+                let mut next = path.clone();
+                next.push(to.clone());
+                stack.push(next);
+            }
+        }
+    }
+    None
+}
+
+/// Checks the operands of the rules against the field types.
+///
+/// Integer literals are coerced to float literals for float fields, e.g. `x > 0` becomes
+/// `x > 0.0` on a `f32`. Other mismatches give an error at the span of the operand.
+pub(super) fn analyze_types(mut ic: IntermediateCode) -> Result<IntermediateCode, syn::Error> {
+    let mut error: Option<syn::Error> = None;
+    let mut push_error = |err: syn::Error| match &mut error {
+        Some(e) => e.combine(err),
+        None => error = Some(err),
+    };
+
+    for field in &mut ic.field_infos {
+        let ty_str = tokens_to_string(&field.ty).replace(' ', "");
+        for rule in &mut field.rules {
+            if let Some(sibling) = rule.sibling() {
+                let Some(sibling_ty) = ic
+                    .all_fields
+                    .iter()
+                    .find(|f| f.ident.as_ref() == Some(sibling))
+                    .map(|f| &f.ty)
+                else {
+                    continue;
+                };
+                // only primitive types are compared by name, paths like `Pressure` and
+                // `uom::si::f64::Pressure` may name the same type and are checked by the
+                // generated code
+                if !is_primitive_type(sibling_ty) || !is_primitive_type(&field.ty) {
+                    rule.sibling_ty = Some(sibling_ty.clone());
+                    continue;
+                }
+                let sibling_ty = tokens_to_string(sibling_ty).replace(' ', "");
+                if sibling_ty != ty_str {
+                    push_error(syn::Error::new_spanned(
+                        &rule.rigth,
+                        format!(
+                            "cannot compare '{}: {}' with '{}: {}', the types differ",
+                            field.field_name, ty_str, sibling, sibling_ty
+                        ),
+                    ));
+                }
+            } else if let Some(unit) = &rule.unit {
+                if is_integer_type(&field.ty) || is_float_type(&field.ty) {
+                    push_error(syn::Error::new_spanned(
+                        unit,
+                        format!(
+                            "the unit '{}' needs a quantity type like 'uom::si::f64::Pressure', found '{}'",
+                            tokens_to_string(unit),
+                            ty_str
+                        ),
+                    ));
+                } else if let Err(err) =
+                    check_literal(&mut rule.rigth, &syn::parse_quote!(f64), "f64")
+                {
+                    push_error(err);
+                }
+            } else if let Err(err) = check_literal(&mut rule.rigth, &field.ty, &ty_str) {
+                push_error(err);
+            }
+        }
+    }
+
+    match error {
+        Some(err) => Err(err),
+        None => Ok(ic),
+    }
+}
+
+/// checks a literal operand against the field type and coerces integer literals for floats
+fn check_literal(expr: &mut syn::Expr, ty: &syn::Type, ty_str: &str) -> Result<(), syn::Error> {
+    let lit = match expr {
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => return check_literal(expr, ty, ty_str),
+        syn::Expr::Paren(p) => return check_literal(&mut p.expr, ty, ty_str),
+        syn::Expr::Lit(syn::ExprLit { lit, .. }) => lit,
+        _ => return Ok(()),
+    };
+
+    let suffix = match &*lit {
+        syn::Lit::Int(i) => i.suffix(),
+        syn::Lit::Float(f) => f.suffix(),
+        _ => return Ok(()),
+    };
+    if !suffix.is_empty() && suffix != ty_str {
+        return Err(syn::Error::new_spanned(
+            &*lit,
+            format!(
+                "the literal suffix '{}' does not match the type '{}' of the field",
+                suffix, ty_str
+            ),
+        ));
+    }
+
+    match &*lit {
+        syn::Lit::Int(i) if is_float_type(ty) => {
+            // `x > 0` on a float field means `x > 0.0`
+            let coerced = syn::LitFloat::new(&format!("{}.0", i.base10_digits()), i.span());
+            *lit = syn::Lit::Float(coerced);
+            Ok(())
+        }
+        syn::Lit::Float(f) if is_integer_type(ty) => Err(syn::Error::new_spanned(
+            f,
+            format!(
+                "expected an integer literal for a field of type '{}', found '{}'",
+                ty_str, f
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
@@ -1,34 +1,100 @@
 use proc_macro::TokenStream;
 use quote::format_ident;
 use quote::quote;
+use quote::quote_spanned;
+use syn::spanned::Spanned;
 
+use super::validator_codegen_numeric::codegen_numeric;
 use super::IntermediateCode;
+use super::ValidationRule;
+use crate::helper::*;
 
 pub(super) fn codegen(ic: IntermediateCode) -> TokenStream {
+    let numeric = codegen_numeric(&ic);
     let type_name = ic.self_type;
     let type_name_as_str = type_name.to_string();
+    let context = ic.context;
+    let field_names: Vec<_> = ic
+        .all_fields
+        .iter()
+        .enumerate()
+        .map(|(i, f)| match &f.ident {
+            Some(ident) => ident.to_string(),
+            None => i.to_string(),
+        })
+        .collect();
 
     let mut code = Vec::new();
 
+    // 0. emit warnings found during the analysis as deprecation notes on the rule, rustc
+    // shows them as "use of deprecated constant `_::<name>`: <msg>"
+    for lint in &ic.lints {
+        let name = syn::Ident::new(&lint.name, proc_macro2::Span::call_site());
+        let msg = &lint.msg;
+        // the usage carries the span of the rule, so the warning points at the user's code
+        let used_name = syn::Ident::new(&lint.name, lint.span);
+        let usage = quote_spanned! {lint.span=> let _ = #used_name;};
+        code.push(quote! {
+            const _: () = {
+                #[deprecated(note = #msg)]
+                #[allow(non_upper_case_globals)]
+                const #name: () = ();
+                #usage
+            };
+        });
+    }
+
     // 1. implement contract helper methods:
     let mut free_contract_functions = Vec::new();
     let mut free_args: Vec<proc_macro2::Ident> = Vec::new();
     let mut contract_functions = Vec::new();
     let mut contract_function_calls = Vec::new();
+    let mut field_contracts = Vec::new();
+    let mut all_rules = Vec::new();
+    let mut sibling_checks = Vec::new();
     for field in ic.field_infos {
         let field_name = field.field_name;
         let ty = field.ty;
         let field_name_str = field_name.to_string();
+        let ty_str = tokens_to_string(&ty).replace(' ', "");
+        let unit = field.unit.as_deref();
+        let unit_contract = match unit {
+            Some(unit) => quote! {Some(#unit)},
+            None => quote! {None},
+        };
+        let rule_contracts: Vec<_> = field
+            .rules
+            .iter()
+            .map(|rule| rule_contract(&field_name_str, rule))
+            .collect();
+        field_contracts.push(quote! {
+            ::engcon::FieldContract {
+                field: #field_name_str,
+                ty: #ty_str,
+                unit: #unit_contract,
+                rules: &[#(#rule_contracts),*],
+            }
+        });
+
         let fn_name = format_ident!("contract_{}", field_name);
         let mut rules = Vec::new();
         let mut free_rules = Vec::new();
 
+        let uses_context = field.rules.iter().any(|r| r.right_uses_context);
+        let numeric = Some(&ty).filter(|ty| is_primitive_type(ty));
         let mut num_args = 0;
         for rule in field.rules {
-            let op = rule.cmp_op;
-            let right = rule.rigth;
-            let op_str = quote! {#op}.to_string();
-            let right_str = quote! {#right}.to_string();
+            let op = &rule.cmp_op;
+            let right = match &rule.sibling_ty {
+                Some(sibling_ty) => {
+                    let check = sibling_check(&field_name, &ty, sibling_ty, &rule);
+                    let check_fn = &check.0;
+                    sibling_checks.push(check.1);
+                    quote_spanned! {rule.rigth.span()=> (*self.#check_fn())}
+                }
+                None => rule.right_operand(&ty),
+            };
+            let right = &right;
 
             if rule.right_is_field_on_self {
                 let ch = char::from_u32(97 + num_args).expect("valid char");
@@ -36,32 +102,67 @@ pub(super) fn codegen(ic: IntermediateCode) -> TokenStream {
                 free_args.push(arg.clone());
                 num_args += 1;
 
+                let err = violation_error(
+                    &type_name_as_str,
+                    &field_name_str,
+                    unit,
+                    numeric,
+                    &rule,
+                    quote! {value},
+                    quote! {#arg},
+                );
                 free_rules.push(quote! {
                     if !(value #op #arg) {
-                        let inject_msg = format!("value={}: '{}' {} '{}'", value, #field_name_str, #op_str, #right_str);
-                        return Err(ValidationError::new(inject_msg, #type_name_as_str.to_owned()));
+                        return Err(#err);
                     }
                 });
             } else {
+                let err = violation_error(
+                    &type_name_as_str,
+                    &field_name_str,
+                    unit,
+                    numeric,
+                    &rule,
+                    quote! {value},
+                    quote! {#right},
+                );
                 free_rules.push(quote! {
                     if !(value #op #right) {
-                        let inject_msg = format!("value={}: '{}' {} '{}'", value, #field_name_str, #op_str, #right_str);
-                        return Err(ValidationError::new(inject_msg, #type_name_as_str.to_owned()));
+                        return Err(#err);
                     }
                 });
             }
 
+            let err = violation_error(
+                &type_name_as_str,
+                &field_name_str,
+                unit,
+                numeric,
+                &rule,
+                quote! {self.#field_name},
+                quote! {#right},
+            );
             rules.push(quote! {
                 if !(self.#field_name #op #right) {
-                    let inject_msg = format!("value={}: '{}' {} '{}'", self.#field_name, #field_name_str, #op_str, #right_str);
-                    return Err(ValidationError::new(inject_msg, #type_name_as_str.to_owned()));
+                    return Err(#err);
+                }
+            });
+            all_rules.push(quote! {
+                if !(self.#field_name #op #right) {
+                    report.push(#err);
                 }
             });
         }
 
+        // fields with rules like `x <= ctx.max_pressure` get the context as extra argument
+        let (ctx_arg, ctx_param) = match (&context, uses_context) {
+            (Some(ctx_ty), true) => (quote! {ctx}, quote! {ctx: &#ctx_ty}),
+            _ => (quote! {}, quote! {}),
+        };
+
         free_contract_functions.push(quote! {
             #[inline]
-            pub fn #fn_name(value: #ty, #(#free_args: #ty,)*) -> Result<(), ValidationError> {
+            pub fn #fn_name(value: #ty, #(#free_args: #ty,)* #ctx_param) -> Result<(), ::engcon::ValidationError> {
                 #(#free_rules)*
                 Ok(())
             }
@@ -70,14 +171,14 @@ pub(super) fn codegen(ic: IntermediateCode) -> TokenStream {
 
         contract_functions.push(quote! {
             #[inline]
-            pub fn #fn_name(&self) -> Result<(), ValidationError> {
+            pub fn #fn_name(&self, #ctx_param) -> Result<(), ::engcon::ValidationError> {
                 #(#rules)*
                 Ok(())
             }
         });
 
         contract_function_calls.push(quote! {
-            self.#fn_name()?;
+            self.#fn_name(#ctx_arg)?;
         });
     }
 
@@ -85,44 +186,302 @@ pub(super) fn codegen(ic: IntermediateCode) -> TokenStream {
         #(#free_contract_functions)*
     });
 
+    // every rule is checked to count its passes and failures, the rules of nested fields are
+    // prefixed with the field like their errors
+    let nested_rules: Vec<_> = ic
+        .nested_fields
+        .iter()
+        .filter_map(|name| {
+            ic.all_fields
+                .iter()
+                .find(|f| f.ident.as_ref() == Some(name))
+        })
+        .map(|f| {
+            let name = f.ident.as_ref().map(|i| i.to_string());
+            let ty = &f.ty;
+            quote! {
+                rules.extend(
+                    (&::engcon::NestedRules::<#ty>(::std::marker::PhantomData))
+                        .rule_ids()
+                        .into_iter()
+                        .map(|id| format!("{}.{}", #name, id)),
+                );
+            }
+        })
+        .collect();
+    let record_rules = quote! {
+        use ::engcon::{NestedContracts as _, NestedValidator as _};
+        let mut rules: Vec<String> = <Self as ::engcon::Contracts>::rules().map(|rule| rule.id.to_owned()).collect();
+        #(#nested_rules)*
+        stats.record_rules(rules.iter().map(String::as_str), &report);
+    };
+    let stats_function = match &context {
+        Some(ctx_ty) => quote! {
+            pub fn validate_with_stats(&self, ctx: &#ctx_ty, stats: &mut ::engcon::ValidationStats) -> Result<(), ::engcon::ValidationError> {
+                let report = ::engcon::ValidatorWithContext::validate_all_with(self, ctx);
+                #record_rules
+                report.into_result()
+            }
+        },
+        None => quote! {
+            pub fn validate_with_stats(&self, stats: &mut ::engcon::ValidationStats) -> Result<(), ::engcon::ValidationError> {
+                let report = ::engcon::Validator::validate_all(self);
+                #record_rules
+                report.into_result()
+            }
+        },
+    };
+
     code.push(quote! {
         impl #type_name {
             #(#contract_functions)*
+
+            #(#sibling_checks)*
+
+            #stats_function
         }
     });
 
-    // 2. Implement the Validator trait
+    // numeric measures of the rules, e.g. the violation for optimizers
+    code.extend(numeric);
+
+    // fields with `#[validate(nested)]` are validated by their own implementation
+    let nested_names: Vec<_> = ic.nested_fields.iter().map(|f| f.to_string()).collect();
+    let nested_fields = &ic.nested_fields;
+    let nested_calls = quote! {
+        #(
+            ::engcon::Validator::validate(&self.#nested_fields)
+                .map_err(|err| err.nested_in(#nested_names, #type_name_as_str))?;
+        )*
+    };
+    let nested_reports = quote! {
+        #(report.push_nested(#nested_names, ::engcon::Validator::validate_all(&self.#nested_fields));)*
+    };
+
+    // 2. Provide the contracts as metadata
     code.push(quote! {
         #[automatically_derived]
-        impl Validator for #type_name {
-            fn validate(&self) -> Result<(), ValidationError> {
-                #(#contract_function_calls)*
-                Ok(())
-            }
+        impl ::engcon::Contracts for #type_name {
+            const TYPE_NAME: &'static str = #type_name_as_str;
+            const FIELDS: &'static [&'static str] = &[#(#field_names),*];
+            const CONTRACTS: &'static [::engcon::FieldContract] = &[#(#field_contracts),*];
         }
     });
 
-    // 3. Implmentation of TryFrom
-    // todo: try to implement this generically, but blocked by error: see engcon/lib.rs
-    code.push(quote! {
-        #[automatically_derived]
-        impl TryFrom<#type_name> for Validated<#type_name> {
-            type Error = ValidationError;
-
-            fn try_from(value: #type_name) -> Result<Self, Self::Error> {
-                match value.validate() {
-                    Ok(_) => {
-                        let reval = unsafe { Validated::new_unchecked(value)};
-                        Ok(reval)
+    // 3. Implement the Validator trait, or ValidatorWithContext if external data is needed
+    if let Some(ctx_ty) = context {
+        code.push(quote! {
+            #[automatically_derived]
+            impl ::engcon::ValidatorWithContext<#ctx_ty> for #type_name {
+                fn validate_with(&self, ctx: &#ctx_ty) -> Result<(), ::engcon::ValidationError> {
+                    #(#contract_function_calls)*
+                    #nested_calls
+                    Ok(())
+                }
+
+                fn validate_all_with(&self, ctx: &#ctx_ty) -> ::engcon::ValidationReport {
+                    let mut report = ::engcon::ValidationReport::new(#type_name_as_str.to_owned());
+                    #(#all_rules)*
+                    #nested_reports
+                    report
+                }
+            }
+        });
+    } else {
+        code.push(quote! {
+            #[automatically_derived]
+            impl ::engcon::Validator for #type_name {
+                fn validate(&self) -> Result<(), ::engcon::ValidationError> {
+                    #(#contract_function_calls)*
+                    #nested_calls
+                    Ok(())
+                }
+
+                fn validate_all(&self) -> ::engcon::ValidationReport {
+                    let mut report = ::engcon::ValidationReport::new(#type_name_as_str.to_owned());
+                    #(#all_rules)*
+                    #nested_reports
+                    report
+                }
+            }
+        });
+
+        // 4. Implmentation of TryFrom, not possible when a context is needed
+        // todo: try to implement this generically, but blocked by error: see engcon/lib.rs
+        code.push(quote! {
+            #[automatically_derived]
+            impl TryFrom<#type_name> for ::engcon::Validated<#type_name> {
+                type Error = ::engcon::ValidationError;
+
+                fn try_from(value: #type_name) -> Result<Self, Self::Error> {
+                    match ::engcon::Validator::validate(&value) {
+                        Ok(_) => {
+                            let reval = unsafe { ::engcon::Validated::new_unchecked(value)};
+                            Ok(reval)
+                        }
+                        Err(err) => Err(err)
                     }
-                    Err(err) => Err(err)
                 }
             }
-        }
-    });
+        });
+    }
 
     quote! {
         #(#code)*
     }
     .into()
 }
+
+/// generates the [engcon::ValidationError] of a violated rule, `value` and `bound` are the
+/// expressions that were compared. The unit of the field is appended to the value and to
+/// numeric bounds, e.g. `value=12 bar: 'pressure' <= '10 bar'`. Fields of a primitive
+/// numeric type also give the value and the bound as `f64`, the bound is rounded to the type
+/// like in the comparison.
+#[allow(clippy::too_many_arguments)]
+fn violation_error(
+    type_name: &str,
+    field_name: &str,
+    unit: Option<&str>,
+    numeric: Option<&syn::Type>,
+    rule: &ValidationRule,
+    value: proc_macro2::TokenStream,
+    bound: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let (mut rhs, id) = rule_id(field_name, rule);
+    let value_unit = match unit {
+        Some(unit) => {
+            if literal_value(&rule.rigth).is_some() && rule.unit.is_none() {
+                rhs = format!("{} {}", rhs, unit);
+            }
+            format!(" {}", unit)
+        }
+        None => String::new(),
+    };
+    let op_str = tokens_to_string(&rule.cmp_op);
+    let op = cmp_op(rule);
+    // literals get the type of the field, e.g. `9_007_199_254_740_993` on a `i64`
+    let (bound, value_num, bound_num) = match numeric {
+        Some(ty) => (
+            quote! {((#bound) as #ty)},
+            quote! {Some(#value as f64)},
+            quote! {Some(((#bound) as #ty) as f64)},
+        ),
+        None => (bound, quote! {None}, quote! {None}),
+    };
+    // quantities like `uom::si::f64::Pressure` only implement `Debug`
+    quote! {
+        {
+            use ::engcon::{FormatDebug as _, FormatDisplay as _};
+            #[allow(clippy::unnecessary_cast)]
+            let (value_num, bound_num, bound) = (#value_num, #bound_num, &#bound);
+            let value = (&::engcon::FormatValue(&#value)).format_value();
+            ::engcon::ValidationError::new(
+                format!("value={}{}: '{}' {} '{}'", value, #value_unit, #field_name, #op_str, #rhs),
+                #type_name.to_owned(),
+            )
+            .with_violation(::engcon::RuleViolation {
+                field: #field_name.to_owned(),
+                rule: #id.to_owned(),
+                op: #op,
+                bound: (&::engcon::FormatValue(bound)).format_value(),
+                value,
+                bound_num,
+                value_num,
+            })
+        }
+    }
+}
+
+/// gets the right hand side as written in the contract and the id of the rule, e.g. `feed_place < trays`
+fn rule_id(field_name: &str, rule: &ValidationRule) -> (String, String) {
+    let rhs = rule.rhs_to_string();
+    let id = format!("{} {} {}", field_name, tokens_to_string(&rule.cmp_op), rhs);
+    (rhs, id)
+}
+
+fn cmp_op(rule: &ValidationRule) -> proc_macro2::TokenStream {
+    match rule.cmp_op {
+        syn::BinOp::Lt(_) => quote! {::engcon::CmpOp::Lt},
+        syn::BinOp::Le(_) => quote! {::engcon::CmpOp::Le},
+        syn::BinOp::Gt(_) => quote! {::engcon::CmpOp::Gt},
+        _ => quote! {::engcon::CmpOp::Ge},
+    }
+}
+
+/// generates the [engcon::RuleContract] describing a rule of the given field
+fn rule_contract(field_name: &str, rule: &ValidationRule) -> proc_macro2::TokenStream {
+    let op = cmp_op(rule);
+    let (rhs, id) = rule_id(field_name, rule);
+    let sibling = sibling_name(rule);
+
+    // the bound of a quantity like `1.0 bar` is not given in the base unit
+    let bound = match literal_value(&rule.rigth).filter(|_| rule.unit.is_none()) {
+        Some(v) => {
+            let v = proc_macro2::Literal::f64_suffixed(v);
+            quote! {Some(#v)}
+        }
+        None => quote! {None},
+    };
+    let sibling = match sibling {
+        Some(name) => quote! {Some(#name)},
+        None => quote! {None},
+    };
+    let uses_context = rule.right_uses_context;
+
+    quote! {
+        ::engcon::RuleContract {
+            id: #id,
+            field: #field_name,
+            op: #op,
+            rhs: #rhs,
+            bound: #bound,
+            sibling: #sibling,
+            uses_context: #uses_context,
+        }
+    }
+}
+
+/// generates a method that gives the sibling of a rule like `x < max_pressure` as the type of
+/// the field. If the types differ, e.g. for a pressure and a temperature, the method is the
+/// only place that fails to compile and the error names both types.
+fn sibling_check(
+    field_name: &syn::Ident,
+    ty: &syn::Type,
+    sibling_ty: &syn::Type,
+    rule: &ValidationRule,
+) -> (syn::Ident, proc_macro2::TokenStream) {
+    let sibling = rule
+        .sibling()
+        .expect("only rules with a sibling have its type");
+    let check_fn = format_ident!("__same_type_{}_{}", field_name, sibling);
+    let msg = format!(
+        "cannot compare '{}: {}' with '{}: {}', the types differ",
+        field_name,
+        tokens_to_string(ty).replace(' ', ""),
+        sibling,
+        tokens_to_string(sibling_ty).replace(' ', ""),
+    );
+    let right = &rule.rigth;
+    let check = quote_spanned! {right.span()=>
+        #[doc(hidden)]
+        #[inline]
+        fn #check_fn(&self) -> &#ty {
+            #[diagnostic::on_unimplemented(message = #msg, label = "compared here")]
+            trait SameType<T> {
+                fn same_type(&self) -> &T;
+            }
+            impl<T> SameType<T> for T {
+                fn same_type(&self) -> &T {
+                    self
+                }
+            }
+            SameType::<#ty>::same_type(&#right)
+        }
+    };
+    (check_fn, check)
+}
+
+/// gets the name of the sibling field for rules like `x < trays`
+fn sibling_name(rule: &ValidationRule) -> Option<String> {
+    rule.sibling().map(|ident| ident.to_string())
+}
@@ -0,0 +1,427 @@
+use quote::quote;
+
+use super::{FieldInfo, IntermediateCode, ValidationRule};
+use crate::helper::*;
+
+/// Generates methods that measure how well the rules hold, e.g. `violations()`.
+///
+/// The methods work on `f64` and are only generated if every validated field has a primitive
+/// numeric type.
+pub(super) fn codegen_numeric(ic: &IntermediateCode) -> Option<proc_macro2::TokenStream> {
+    let numeric = ic
+        .field_infos
+        .iter()
+        .all(|f| is_integer_type(&f.ty) || is_float_type(&f.ty));
+    if !numeric {
+        return None;
+    }
+
+    let type_name = &ic.self_type;
+    let (ctx_param, ctx_arg) = match &ic.context {
+        Some(ctx_ty) => (quote! {ctx: &#ctx_ty,}, quote! {ctx,}),
+        None => (quote! {}, quote! {}),
+    };
+    let ctx_unused = ic.context.as_ref().map(|_| quote! {let _ = ctx;});
+
+    let mut violations = vec![];
+    let mut margins = vec![];
+    let mut boundaries = vec![];
+    let mut holds = vec![];
+    for (i, field) in ic.field_infos.iter().enumerate() {
+        for (j, rule) in field.rules.iter().enumerate() {
+            violations.push(rule_violation(field, rule));
+            margins.push(rule_margin(field, rule, i, j));
+            boundaries.push(rule_boundary(field, rule, i, j));
+            holds.push(rule_holds(field, rule));
+        }
+    }
+    let setters: Vec<_> = ic
+        .field_infos
+        .iter()
+        .map(|f| {
+            let field_name = &f.field_name;
+            let field_name_str = field_name.to_string();
+            let ty = &f.ty;
+            quote! {#field_name_str => self.#field_name = value as #ty,}
+        })
+        .collect();
+
+    let order = dependency_order(ic);
+    let repairs: Vec<_> = order
+        .iter()
+        .map(|&i| field_repair(&ic.field_infos[i]))
+        .collect();
+    let samples: Vec<_> = order
+        .iter()
+        .map(|&i| field_sample(&ic.field_infos[i]))
+        .collect();
+    let ctx_ty = match &ic.context {
+        Some(ctx_ty) => quote! {#ctx_ty},
+        None => quote! {()},
+    };
+
+    Some(quote! {
+        #[allow(clippy::unnecessary_cast)]
+        impl #type_name {
+            /// The violation of every rule in the order of the [rules](engcon::Contracts::rules).
+            ///
+            /// A satisfied rule gives 0, otherwise the distance to the closest valid value is
+            /// given, e.g. `feed_place - trays + 1` for `x < trays` on an integer field.
+            pub fn violations(&self, #ctx_param) -> Vec<f64> {
+                #ctx_unused
+                vec![#(#violations),*]
+            }
+
+            /// The sum of all [violations](Self::violations), 0 if the value is valid
+            pub fn violation(&self, #ctx_param) -> f64 {
+                self.violations(#ctx_arg).iter().sum()
+            }
+
+            /// The [violations](Self::violations) scaled by the [Penalty](engcon::Penalty), usable
+            /// as penalty term in an optimizer
+            pub fn penalty(&self, #ctx_param penalty: &::engcon::Penalty) -> f64 {
+                penalty.apply(&self.violations(#ctx_arg))
+            }
+
+            /// The slack of every rule relative to its bound in the order of the
+            /// [rules](engcon::Contracts::rules).
+            ///
+            /// A negative slack means the rule is violated, see [Margin](engcon::Margin).
+            pub fn margins(&self, #ctx_param) -> Vec<::engcon::Margin> {
+                #ctx_unused
+                vec![#(#margins),*]
+            }
+
+            /// The rules whose slack is below the threshold, e.g. `0.02` for rules that are
+            /// nearly active within 2% of their bound
+            pub fn active_rules(&self, #ctx_param threshold: f64) -> Vec<::engcon::Margin> {
+                ::engcon::Margin::below(self.margins(#ctx_arg), threshold)
+            }
+
+            /// Clamps every field into its valid range, see [repair_with](Self::repair_with)
+            pub fn repair(&mut self, #ctx_param) -> ::engcon::RepairReport {
+                self.repair_with(#ctx_arg 0.0)
+            }
+
+            /// Clamps every field to the closest valid value and reports the changed fields.
+            ///
+            /// Fields are repaired in dependency order, e.g. `trays` before `feed_place` with
+            /// `x < trays`. Strict rules on float fields keep a distance of `epsilon`, at least
+            /// the smallest representable step, to the bound.
+            pub fn repair_with(&mut self, #ctx_param epsilon: f64) -> ::engcon::RepairReport {
+                #ctx_unused
+                let mut report = ::engcon::RepairReport::default();
+                #(#repairs)*
+                report
+            }
+        }
+
+        #[automatically_derived]
+        #[allow(clippy::unnecessary_cast)]
+        impl ::engcon::Sample<#ctx_ty> for #type_name {
+            fn sample_fields(
+                &mut self,
+                ctx: &#ctx_ty,
+                draw: &mut dyn FnMut(&::engcon::SampleRange) -> f64,
+            ) -> bool {
+                let _ = ctx;
+                let mut valid = true;
+                #(#samples)*
+                valid
+            }
+        }
+
+        #[automatically_derived]
+        #[allow(clippy::unnecessary_cast)]
+        impl ::engcon::BoundaryCases<#ctx_ty> for #type_name {
+            fn boundary_values(&self, ctx: &#ctx_ty) -> Vec<::engcon::BoundaryValue> {
+                let _ = ctx;
+                let mut values = vec![];
+                #(values.extend(#boundaries);)*
+                values
+            }
+
+            fn set_boundary_value(&mut self, field: &str, value: f64) {
+                match field {
+                    #(#setters)*
+                    _ => {}
+                }
+            }
+
+            fn contracts_hold(&self, ctx: &#ctx_ty) -> bool {
+                let _ = ctx;
+                true #(&& #holds)*
+            }
+        }
+    })
+}
+
+/// generates the code that draws a field from the range given by its rules
+fn field_sample(field: &FieldInfo) -> proc_macro2::TokenStream {
+    let field_name = &field.field_name;
+    let field_name_str = field_name.to_string();
+    let ty = &field.ty;
+    let integer = is_integer_type(ty);
+
+    let restrictions: Vec<_> = field
+        .rules
+        .iter()
+        .map(|rule| {
+            let right = &rule.rigth;
+            let strict = rule.is_strict();
+            if rule.is_upper_bound() {
+                quote! {range.restrict_upper(((#right) as #ty) as f64, #strict);}
+            } else {
+                quote! {range.restrict_lower(((#right) as #ty) as f64, #strict);}
+            }
+        })
+        .collect();
+    let checks = field_checks(field);
+
+    quote! {
+        {
+            let mut range = ::engcon::SampleRange::new(#field_name_str, #integer);
+            #(#restrictions)*
+            // rounding to the field type may hit a strict bound, draw again in that case
+            let mut drawn = false;
+            for _ in 0..::engcon::SampleRange::MAX_TRIES {
+                self.#field_name = draw(&range) as #ty;
+                if #checks {
+                    drawn = true;
+                    break;
+                }
+            }
+            valid &= drawn;
+        }
+    }
+}
+
+/// generates the check of every rule of the field in its type, like in the generated
+/// `validate()`
+fn field_checks(field: &FieldInfo) -> proc_macro2::TokenStream {
+    let field_name = &field.field_name;
+    let checks = field.rules.iter().map(|rule| {
+        let op = &rule.cmp_op;
+        let right = &rule.rigth;
+        quote! {(self.#field_name #op #right)}
+    });
+    quote! {#(#checks)&&*}
+}
+
+/// generates the expression that gives the violation of a rule as `f64`
+fn rule_violation(field: &FieldInfo, rule: &ValidationRule) -> proc_macro2::TokenStream {
+    let field_name = &field.field_name;
+    let ty = &field.ty;
+    let op = &rule.cmp_op;
+    let right = &rule.rigth;
+    let step = if rule.is_strict() {
+        step(field, None)
+    } else {
+        quote! {0.0}
+    };
+    let distance = if rule.is_upper_bound() {
+        quote! {value - bound + #step}
+    } else {
+        quote! {bound - value + #step}
+    };
+
+    quote! {
+        if self.#field_name #op #right {
+            0.0
+        } else {
+            let value = self.#field_name as f64;
+            let bound = ((#right) as #ty) as f64;
+            #distance
+        }
+    }
+}
+
+/// generates the [engcon::Margin] of a rule, `i` and `j` are the indices of the field and
+/// the rule in the contracts
+fn rule_margin(
+    field: &FieldInfo,
+    rule: &ValidationRule,
+    i: usize,
+    j: usize,
+) -> proc_macro2::TokenStream {
+    let field_name = &field.field_name;
+    let ty = &field.ty;
+    let right = &rule.rigth;
+    let slack = if rule.is_upper_bound() {
+        quote! {bound - value}
+    } else {
+        quote! {value - bound}
+    };
+
+    quote! {
+        {
+            let value = self.#field_name as f64;
+            let bound = ((#right) as #ty) as f64;
+            ::engcon::Margin::new(&<Self as ::engcon::Contracts>::CONTRACTS[#i].rules[#j], #slack, bound)
+        }
+    }
+}
+
+/// generates the values just outside, on and just inside the bound of a rule, `i` and `j`
+/// are the indices of the field and the rule in the contracts
+fn rule_boundary(
+    field: &FieldInfo,
+    rule: &ValidationRule,
+    i: usize,
+    j: usize,
+) -> proc_macro2::TokenStream {
+    let ty = &field.ty;
+    let right = &rule.rigth;
+    let step = step(field, None);
+
+    quote! {
+        {
+            let rule = &<Self as ::engcon::Contracts>::CONTRACTS[#i].rules[#j];
+            let bound = ((#right) as #ty) as f64;
+            let step = #step;
+            [bound - step, bound, bound + step].map(|value| ::engcon::BoundaryValue { rule, value })
+        }
+    }
+}
+
+/// generates the check of a rule in `f64` arithmetic, both sides are rounded to the field
+/// type first like in the generated `validate()`
+fn rule_holds(field: &FieldInfo, rule: &ValidationRule) -> proc_macro2::TokenStream {
+    let field_name = &field.field_name;
+    let ty = &field.ty;
+    let op = &rule.cmp_op;
+    let right = &rule.rigth;
+
+    quote! {
+        ((self.#field_name as f64) #op (((#right) as #ty) as f64))
+    }
+}
+
+/// generates the code that clamps a field into the range given by its rules
+fn field_repair(field: &FieldInfo) -> proc_macro2::TokenStream {
+    let field_name = &field.field_name;
+    let field_name_str = field_name.to_string();
+    let ty = &field.ty;
+
+    let clamps: Vec<_> = field
+        .rules
+        .iter()
+        .map(|rule| {
+            let right = &rule.rigth;
+            let step = if rule.is_strict() {
+                step(field, Some(quote! {epsilon}))
+            } else {
+                quote! {0.0}
+            };
+            if rule.is_upper_bound() {
+                quote! {
+                    let bound = ((#right) as #ty) as f64;
+                    hi = hi.min(bound - #step);
+                }
+            } else {
+                quote! {
+                    let bound = ((#right) as #ty) as f64;
+                    lo = lo.max(bound + #step);
+                }
+            }
+        })
+        .collect();
+
+    let checks = field_checks(field);
+    // a NaN is neither below nor above a bound
+    let nan = if is_float_type(ty) {
+        quote! {|| value.is_nan()}
+    } else {
+        quote! {}
+    };
+    let (up, down) = if is_float_type(ty) {
+        (
+            quote! {self.#field_name.next_up()},
+            quote! {self.#field_name.next_down()},
+        )
+    } else {
+        (
+            quote! {self.#field_name.saturating_add(1)},
+            quote! {self.#field_name.saturating_sub(1)},
+        )
+    };
+
+    quote! {
+        {
+            let mut lo = f64::NEG_INFINITY;
+            let mut hi = f64::INFINITY;
+            #({#clamps})*
+            let value = self.#field_name as f64;
+            if lo > hi #nan {
+                report.unresolved.push(#field_name_str);
+            } else if value < lo || value > hi {
+                let old = self.#field_name;
+                self.#field_name = value.clamp(lo, hi) as #ty;
+                // the cast may round onto the bound, e.g. a `i64` beyond 2^53, step to the
+                // valid side
+                if !(#checks) {
+                    self.#field_name = if value < lo { #up } else { #down };
+                }
+                report.changes.push(::engcon::RepairChange {
+                    field: #field_name_str,
+                    old: format!("{}", old),
+                    new: format!("{}", self.#field_name),
+                });
+                if !(#checks) {
+                    report.unresolved.push(#field_name_str);
+                }
+            }
+        }
+    }
+}
+
+/// gets the indices of the fields in an order where the siblings of a rule come first,
+/// fields in a cycle of non-strict rules stay in declaration order
+fn dependency_order(ic: &IntermediateCode) -> Vec<usize> {
+    let n = ic.field_infos.len();
+    let depends_on = |i: usize, j: usize| {
+        i != j
+            && ic.field_infos[i]
+                .rules
+                .iter()
+                .any(|r| r.sibling() == Some(&ic.field_infos[j].field_name))
+    };
+
+    let mut order: Vec<usize> = vec![];
+    while order.len() < n {
+        let ready = (0..n).filter(|i| !order.contains(i)).find(|&i| {
+            (0..n)
+                .filter(|j| !order.contains(j))
+                .all(|j| !depends_on(i, j))
+        });
+        match ready {
+            Some(i) => order.push(i),
+            // cycle: take the first remaining field
+            None => order.push(
+                (0..n)
+                    .find(|i| !order.contains(i))
+                    .expect("a field is left"),
+            ),
+        }
+    }
+    order
+}
+
+/// generates the smallest step from a bound to the next valid value of a strict rule, it
+/// expects the bound as `f64` in the variable `bound`. For floats an expression with a
+/// minimal distance like `epsilon` can be given.
+pub(super) fn step(
+    field: &FieldInfo,
+    min: Option<proc_macro2::TokenStream>,
+) -> proc_macro2::TokenStream {
+    let ty = &field.ty;
+    if is_integer_type(ty) {
+        quote! {1.0}
+    } else {
+        let smallest = quote! {(<#ty>::EPSILON as f64) * bound.abs().max(1.0)};
+        match min {
+            Some(min) => quote! {(#smallest).max(#min)},
+            None => smallest,
+        }
+    }
+}
@@ -5,6 +5,19 @@ use super::PartialAST;
 pub(super) fn filter_ast(input: DeriveInput) -> Result<PartialAST, syn::Error> {
     let self_type = input.ident;
 
+    // struct level options, e.g. `#[validate(context = PlantLimits)]`
+    let mut context = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("validate")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("context") {
+                context = Some(meta.value()?.parse::<syn::Type>()?);
+                Ok(())
+            } else {
+                Err(meta.error("unsupported validate option, expected `context = Type`"))
+            }
+        })?;
+    }
+
     let struct_: syn::DataStruct = match input.data {
         syn::Data::Struct(data) => data,
         _ => {
@@ -15,6 +28,8 @@ pub(super) fn filter_ast(input: DeriveInput) -> Result<PartialAST, syn::Error> {
         }
     };
 
+    let all_fields: Vec<syn::Field> = struct_.fields.iter().cloned().collect();
+
     // filter fields that have validated rule
     let validated_fields: Vec<syn::Field> = struct_
         .fields
@@ -27,7 +42,50 @@ pub(super) fn filter_ast(input: DeriveInput) -> Result<PartialAST, syn::Error> {
         .cloned()
         .collect();
 
-    if validated_fields.is_empty() {
+    // the unit is stored in the contract of the field, which only exists with rules
+    let mut error: Option<syn::Error> = None;
+    for field in &struct_.fields {
+        let has_rules = field
+            .attrs
+            .iter()
+            .any(|a| a.path().is_ident("validate_value"));
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("unit")) {
+            if has_rules {
+                continue;
+            }
+            let err = syn::Error::new_spanned(
+                attr,
+                "#[unit] needs a #[validate_value(...)] attribute on the same field",
+            );
+            match &mut error {
+                Some(e) => e.combine(err),
+                None => error = Some(err),
+            }
+        }
+    }
+    if let Some(err) = error {
+        return Err(err);
+    }
+
+    // fields whose type validates itself, e.g. `#[validate(nested)] feed: Fraction`
+    let mut nested_fields = vec![];
+    for field in &struct_.fields {
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("validate")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("nested") {
+                    match &field.ident {
+                        Some(ident) => nested_fields.push(ident.clone()),
+                        None => return Err(meta.error("nested validation needs a named field")),
+                    }
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported validate option on a field, expected `nested`"))
+                }
+            })?;
+        }
+    }
+
+    if validated_fields.is_empty() && nested_fields.is_empty() {
         Err(syn::Error::new_spanned(
             self_type,
             "Use at least one validate_value() attribute helper".to_owned(),
@@ -35,7 +93,10 @@ pub(super) fn filter_ast(input: DeriveInput) -> Result<PartialAST, syn::Error> {
     } else {
         Ok(PartialAST {
             self_type,
+            context,
+            all_fields,
             validated_fields,
+            nested_fields,
         })
     }
 }
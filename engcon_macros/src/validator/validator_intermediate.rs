@@ -4,16 +4,39 @@ use super::{FieldInfo, IntermediateCode, PartialAST, ValidationRule};
 
 pub(super) fn intermediate_code(ast: PartialAST) -> Result<IntermediateCode, syn::Error> {
     let self_type = ast.self_type;
+    let context = ast.context;
 
     let mut field_infos = vec![];
     let mut error: Option<syn::Error> = None;
     for field in &ast.validated_fields {
-        // we know there is only validated_value attributes left:
-        // we know they are meta lists with commas:
-
+        // rules are meta lists with commas, other attributes are ignored
         let mut rules = vec![];
+        let mut unit = None;
         for attr in &field.attrs {
-            match parse_validate_rules_from_attribute(attr) {
+            if attr.path().is_ident("unit") {
+                match attr.parse_args::<syn::LitStr>() {
+                    Ok(lit) => unit = Some(lit.value()),
+                    Err(err) => match &mut error {
+                        Some(e) => e.combine(err),
+                        None => error = Some(err),
+                    },
+                }
+                continue;
+            }
+            if !attr.path().is_ident("validate_value") {
+                continue;
+            }
+
+            let parsed = parse_validate_rules_from_attribute(attr).and_then(|r| {
+                match r.iter().find(|rule| rule.right_uses_context) {
+                    Some(rule) if context.is_none() => Err(syn::Error::new_spanned(
+                        &rule.rigth,
+                        "Using 'ctx' requires a #[validate(context = Type)] attribute on the struct",
+                    )),
+                    _ => Ok(r),
+                }
+            });
+            match parsed {
                 Ok(r) => {
                     rules.extend(r);
                 }
@@ -32,6 +55,7 @@ pub(super) fn intermediate_code(ast: PartialAST) -> Result<IntermediateCode, syn
             field_name: field.ident.clone().unwrap(),
             ty: field.ty.clone(),
             rules,
+            unit,
         });
     }
 
@@ -39,7 +63,11 @@ pub(super) fn intermediate_code(ast: PartialAST) -> Result<IntermediateCode, syn
         Some(err) => Err(err),
         None => Ok(IntermediateCode {
             self_type,
+            context,
+            all_fields: ast.all_fields,
             field_infos,
+            nested_fields: ast.nested_fields,
+            lints: vec![],
         }),
     }
 }
@@ -0,0 +1,42 @@
+// the generated code must not depend on the names imported by the user
+use engcon::{Validatable, Validated, ValidationError, Validator};
+
+pub struct PlantLimits {
+    pub max_pressure: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Validatable)]
+pub struct DistillationColumn {
+    #[validate_value(x >= 3)]
+    pub trays: i32,
+    #[validate_value(x < trays, x >= 1)]
+    pub feed_place: i32,
+    #[validate_value(x > 0.0, x < 1.0)]
+    pub distiliate_to_feed_ratio: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Validatable)]
+#[validate(context = PlantLimits)]
+pub struct Vessel {
+    #[validate_value(x > 0.0, x <= ctx.max_pressure)]
+    pub design_pressure: f64,
+    #[validate(nested)]
+    pub column: DistillationColumn,
+}
+
+fn main() {
+    let column = DistillationColumn {
+        trays: 10,
+        feed_place: 5,
+        distiliate_to_feed_ratio: 0.5,
+    };
+    let validated: Result<Validated<DistillationColumn>, ValidationError> =
+        column.clone().try_into_validated();
+    assert!(validated.is_ok());
+
+    let vessel = Vessel {
+        design_pressure: 12.0,
+        column,
+    };
+    assert!(Validated::try_new_with(vessel, &PlantLimits { max_pressure: 10.0 }).is_err());
+}
@@ -0,0 +1,8 @@
+//! Snapshots of the compile errors, run `TRYBUILD=overwrite cargo test` to update them.
+
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+    t.pass("tests/pass/*.rs");
+}
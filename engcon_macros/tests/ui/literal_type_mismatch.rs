@@ -0,0 +1,9 @@
+use engcon_macros::Validatable;
+
+#[derive(Validatable)]
+struct DistillationColumn {
+    #[validate_value(x >= 2.5)]
+    trays: i32,
+}
+
+fn main() {}
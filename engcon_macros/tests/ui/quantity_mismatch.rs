@@ -0,0 +1,11 @@
+use engcon::uom::si::f64::{Pressure, ThermodynamicTemperature};
+use engcon_macros::Validatable;
+
+#[derive(Validatable)]
+struct Vessel {
+    design_pressure: Pressure,
+    #[validate_value(x < design_pressure)]
+    temperature: ThermodynamicTemperature,
+}
+
+fn main() {}
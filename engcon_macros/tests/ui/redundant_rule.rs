@@ -0,0 +1,11 @@
+#![deny(deprecated)]
+
+use engcon_macros::Validatable;
+
+#[derive(Validatable)]
+struct Pump {
+    #[validate_value(x > 0.0, x > 1.5)]
+    head: f64,
+}
+
+fn main() {}
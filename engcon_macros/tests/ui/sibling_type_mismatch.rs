@@ -0,0 +1,10 @@
+use engcon_macros::Validatable;
+
+#[derive(Validatable)]
+struct DistillationColumn {
+    trays: f64,
+    #[validate_value(x < trays)]
+    feed_place: i32,
+}
+
+fn main() {}
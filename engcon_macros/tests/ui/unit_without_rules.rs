@@ -0,0 +1,11 @@
+use engcon_macros::Validatable;
+
+#[derive(Validatable)]
+struct Vessel {
+    #[validate_value(x > 0.0)]
+    volume: f64,
+    #[unit("bar")]
+    pressure: f64,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use engcon_macros::Validatable;
+
+#[derive(Validatable)]
+struct A {
+    trays: i32,
+    #[validate_value(x < trayz)]
+    feed_place: i32,
+}
+
+fn main() {}
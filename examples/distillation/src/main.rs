@@ -1,6 +1,6 @@
 use engcon::*;
 
-#[derive(Debug, Clone, Default, Copy, PartialEq, Validatable)]
+#[derive(Debug, Clone, Default, Copy, PartialEq, serde::Deserialize, Validatable)]
 pub struct DistillationColumn {
     #[validate_value(x >= 3)]
     pub trays: i32,
@@ -15,34 +15,28 @@ pub struct DistillationColumn {
     pub distiliate_to_feed_ratio: f32,
 }
 
+/// the design cases, e.g. exported from a spreadsheet
+const DESIGN_CASES: &str = "\
+trays, feed_place, reflux_ratio, distiliate_to_feed_ratio
+0, 0, 0.0, 0.0
+20, 25, 0.0, 0.0
+20, 10, 1.5, 0.9
+40, 20, 0.75, 1.0
+";
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let dcs = [
-        DistillationColumn::default(),
-        DistillationColumn {
-            trays: 20,
-            feed_place: 25,
-            ..Default::default()
-        },
-        DistillationColumn {
-            trays: 20,
-            feed_place: 10,
-            distiliate_to_feed_ratio: 0.9,
-            reflux_ratio: 1.5,
-        },
-        DistillationColumn {
-            trays: 40,
-            feed_place: 20,
-            distiliate_to_feed_ratio: 1.,
-            reflux_ratio: 0.75,
-        },
-    ];
+    let batch = tabular::validate_csv::<DistillationColumn, _>(DESIGN_CASES.as_bytes())?;
 
-    for dc in &dcs {
-        println!("{dc:?}");
-        match dc.validate() {
-            Ok(_) => println!("is valid!"),
-            Err(err) => println!("not valid: {}", err),
-        }
+    for (row, dc) in batch.valid() {
+        println!("row {row} is valid: {:?}", **dc);
+    }
+    for err in batch.errors() {
+        println!("not valid: {err}");
     }
+    println!(
+        "{} of {} design cases are valid",
+        batch.valid().len(),
+        batch.rows()
+    );
     Ok(())
 }